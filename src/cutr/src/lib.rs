@@ -1,22 +1,259 @@
 use crate::Extract::*;
 use clap::{App, Arg};
-use std::{error::Error, ops::Range};
+use regex::bytes::Regex;
+use serde_json::{Map, Value};
+use std::{
+    error::Error,
+    fs::File,
+    io::{self, BufRead, BufReader, BufWriter, Read, Write},
+    ops::Range,
+};
+use unicode_segmentation::UnicodeSegmentation;
 
-type Res<T> = Result<T, Box<dyn Error>>;
-type PositionList = Vec<Range<usize>>;
+pub type Res<T> = Result<T, Box<dyn Error>>;
+pub type PositionList = Vec<Range<usize>>;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum Extract {
     Fields(PositionList),
     Bytes(PositionList),
     Chars(PositionList),
 }
 
+// how a record is split into fields
+#[derive(Debug, Clone)]
+pub enum Delimiter {
+    // any non-empty string, matched exactly
+    Literal(Vec<u8>),
+    // every match of the pattern separates two fields
+    Regex(Regex),
+    // fixed width columns, given as the char offset each one starts at.
+    // the last column runs to the end of the record
+    Columns(Vec<usize>),
+    // columns taken from where the words of each file's first record start,
+    // empty until that record has been seen
+    Aligned(Vec<usize>),
+}
+
+impl Delimiter {
+    // columns of the given char widths, plus one more for whatever follows
+    pub fn widths(widths: &[usize]) -> Self {
+        let starts = widths.iter().fold(vec![0], |mut starts, width| {
+            starts.push(starts.last().unwrap() + width);
+            starts
+        });
+        Delimiter::Columns(starts)
+    }
+
+    // columns lined up with the words of a header, see column_starts
+    pub fn aligned(header: &[u8]) -> Self {
+        Delimiter::Aligned(column_starts(header))
+    }
+
+    fn split<'a>(&self, record: &'a [u8]) -> Vec<&'a [u8]> {
+        match self {
+            Delimiter::Literal(delim) => split_literal(record, delim),
+            Delimiter::Regex(re) => re.split(record).collect(),
+            Delimiter::Columns(starts) => split_columns(record, starts, false),
+            Delimiter::Aligned(starts) => split_columns(record, starts, true),
+        }
+    }
+
+    // the bytes placed between selected fields on output. patterns and
+    // columns have no single representation so we fall back to a tab
+    fn joiner(&self) -> &[u8] {
+        match self {
+            Delimiter::Literal(delim) => delim,
+            _ => b"\t",
+        }
+    }
+}
+
+// how the selected pieces of each record are written out
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    // joined with the output delimiter, like cut
+    Text,
+    // a single array holding one entry per record
+    Json,
+    // one JSON value per line
+    Jsonl,
+    Csv,
+    Tsv,
+}
+
+// picks the selected pieces out of each record it is given. this is all
+// of cutr short of reading files and formatting output, so it can be
+// reused as a library, eg
+//
+//     let fields = Extractor::new(Fields(parse_pos("3,1")?))
+//         .delimiter(Delimiter::Literal(b",".to_vec()))
+//         .ordered(true);
+//     assert_eq!(fields.cut(b"a,b,c"), Some(b"c,a".to_vec()));
+#[derive(Debug, Clone)]
+pub struct Extractor {
+    extract: Extract,
+    delimiter: Delimiter,
+    output_delimiter: Option<Vec<u8>>,
+    complement: bool,
+    ordered: bool,
+    graphemes: bool,
+    raw_bytes: bool,
+    only_delimited: bool,
+}
+
+impl Extractor {
+    // splits fields on tabs and behaves like cut until told otherwise
+    pub fn new(extract: Extract) -> Self {
+        Extractor {
+            extract,
+            delimiter: Delimiter::Literal(b"\t".to_vec()),
+            output_delimiter: None,
+            complement: false,
+            ordered: false,
+            graphemes: false,
+            raw_bytes: false,
+            only_delimited: false,
+        }
+    }
+
+    pub fn delimiter(mut self, delimiter: Delimiter) -> Self {
+        self.delimiter = delimiter;
+        self
+    }
+
+    // what cut puts between pieces instead of the input delimiter
+    pub fn output_delimiter(mut self, delim: &[u8]) -> Self {
+        self.output_delimiter = Some(delim.to_vec());
+        self
+    }
+
+    // select everything except the listed positions
+    pub fn complement(mut self, complement: bool) -> Self {
+        self.complement = complement;
+        self
+    }
+
+    // keep the list order and any repeats. takes precedence over complement
+    pub fn ordered(mut self, ordered: bool) -> Self {
+        self.ordered = ordered;
+        self
+    }
+
+    // count chars as grapheme clusters
+    pub fn graphemes(mut self, graphemes: bool) -> Self {
+        self.graphemes = graphemes;
+        self
+    }
+
+    // let bytes split UTF-8 sequences rather than replacing the pieces
+    pub fn raw_bytes(mut self, raw_bytes: bool) -> Self {
+        self.raw_bytes = raw_bytes;
+        self
+    }
+
+    // drop records that contain no delimiter instead of passing them through
+    pub fn only_delimited(mut self, only_delimited: bool) -> Self {
+        self.only_delimited = only_delimited;
+        self
+    }
+
+    // the selected fields, or runs of bytes or chars, of a record.
+    // returns None when the record should be left out of the output entirely
+    pub fn extract(&self, record: &[u8]) -> Option<Vec<Vec<u8>>> {
+        match &self.extract {
+            Fields(list) => {
+                let fields = self.delimiter.split(record);
+                // like cut, a record without any delimiter is passed through untouched
+                if fields.len() == 1 {
+                    return (!self.only_delimited).then(|| vec![record.to_vec()]);
+                }
+                let selected = self
+                    .select(list, fields.len())
+                    .into_iter()
+                    .map(|i| fields[i].to_vec())
+                    .collect();
+                Some(selected)
+            }
+            Bytes(list) => {
+                // each run is checked on its own so that the tail of one character
+                // can never pair up with the head of another
+                let selected: Vec<_> = runs(&self.select(list, record.len()))
+                    .into_iter()
+                    .map(|r| {
+                        if self.raw_bytes {
+                            record[r].to_vec()
+                        } else {
+                            lossy(&record[r]).into_bytes()
+                        }
+                    })
+                    .collect();
+                Some(selected)
+            }
+            Chars(list) => {
+                let text = String::from_utf8_lossy(record);
+                let chars: Vec<&str> = if self.graphemes {
+                    text.graphemes(true).collect()
+                } else {
+                    // one str per code point
+                    text.split_inclusive(|_| true).collect()
+                };
+                let selected: Vec<_> = runs(&self.select(list, chars.len()))
+                    .into_iter()
+                    .map(|r| chars[r].concat().into_bytes())
+                    .collect();
+                Some(selected)
+            }
+        }
+    }
+
+    // the selected pieces of a record joined up the way cut prints them
+    pub fn cut(&self, record: &[u8]) -> Option<Vec<u8>> {
+        self.extract(record)
+            .map(|pieces| pieces.join(self.joiner()))
+    }
+
+    // what goes between pieces: fields keep their delimiter while runs
+    // of bytes or chars are simply put back together
+    fn joiner(&self) -> &[u8] {
+        match (&self.output_delimiter, &self.extract) {
+            (Some(delim), _) => delim,
+            (None, Fields(_)) => self.delimiter.joiner(),
+            (None, _) => b"",
+        }
+    }
+
+    // the positions in 0..len picked out by the list, or all the others when
+    // complementing. as cut does, these are in ascending order and each at most
+    // once, unless ordered where the list is followed as written like awk would
+    fn select(&self, list: &PositionList, len: usize) -> Vec<usize> {
+        let mut selected: Vec<usize> = list
+            .iter()
+            .flat_map(|r| r.start.min(len)..r.end.min(len))
+            .collect();
+        if self.ordered {
+            return selected;
+        }
+        selected.sort_unstable();
+        selected.dedup();
+
+        if self.complement {
+            (0..len)
+                .filter(|i| selected.binary_search(i).is_err())
+                .collect()
+        } else {
+            selected
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct Config {
     files: Vec<String>,
-    delimiter: u8,
-    extract: Extract,
+    extractor: Extractor,
+    format: Format,
+    header: bool,
+    zero_terminated: bool,
 }
 
 pub fn get_args() -> Res<Config> {
@@ -29,101 +266,524 @@ pub fn get_args() -> Res<Config> {
                 .short("d")
                 .long("delim")
                 .takes_value(true)
-                .default_value("\t"),
+                .help("Field delimiter, may be more than one character [default: \\t]"),
+        )
+        .arg(
+            Arg::with_name("regex_delim")
+                .long("regex-delim")
+                .value_name("PATTERN")
+                .takes_value(true)
+                .conflicts_with("delim")
+                .help("Split fields on every match of PATTERN"),
+        )
+        .arg(
+            Arg::with_name("widths")
+                .long("widths")
+                .value_name("LIST")
+                .takes_value(true)
+                .conflicts_with_all(&["delim", "regex_delim"])
+                .requires("fields")
+                .help("Split fields into columns of the given char widths, eg 20,5"),
+        )
+        .arg(
+            Arg::with_name("aligned")
+                .long("aligned")
+                .conflicts_with_all(&["delim", "regex_delim", "widths"])
+                .requires("fields")
+                .help("Split fields into columns aligned with the words of the first line"),
         )
         .arg(
             Arg::with_name("bytes")
                 .short("b")
                 .long("bytes")
-                .multiple(true)
-                .use_delimiter(true)
-                .conflicts_with_all(&["chars, fields"]),
+                .takes_value(true)
+                .conflicts_with_all(&["chars", "fields"]),
         )
         .arg(
             Arg::with_name("chars")
                 .short("c")
                 .long("chars")
-                .multiple(true)
-                .use_delimiter(true)
-                .conflicts_with_all(&["bytes, fields"]),
+                .takes_value(true)
+                .conflicts_with_all(&["bytes", "fields"]),
         )
         .arg(
             Arg::with_name("fields")
                 .short("f")
                 .long("fields")
-                .multiple(true)
-                .use_delimiter(true)
-                .conflicts_with_all(&["bytes, chars"]),
+                .takes_value(true)
+                .conflicts_with_all(&["bytes", "chars"]),
+        )
+        .arg(
+            Arg::with_name("complement")
+                .long("complement")
+                .help("Select everything except the given positions"),
+        )
+        .arg(
+            Arg::with_name("ordered")
+                .long("ordered")
+                .conflicts_with("complement")
+                .help("Keep positions in the order given, allowing repeats"),
+        )
+        .arg(
+            Arg::with_name("graphemes")
+                .long("graphemes")
+                .requires("chars")
+                .help("Count --chars in grapheme clusters rather than code points"),
+        )
+        .arg(
+            Arg::with_name("raw_bytes")
+                .long("raw-bytes")
+                .requires("bytes")
+                .help("Emit --bytes as is, even when that splits a UTF-8 sequence"),
+        )
+        .arg(
+            Arg::with_name("output_delim")
+                .long("output-delimiter")
+                .value_name("STR")
+                .takes_value(true)
+                .help("Join fields, or runs of bytes and chars, with STR"),
+        )
+        .arg(
+            Arg::with_name("only_delimited")
+                .short("s")
+                .long("only-delimited")
+                .requires("fields")
+                .help("Skip records that contain no delimiter"),
+        )
+        .arg(
+            Arg::with_name("output_format")
+                .long("output-format")
+                .value_name("FORMAT")
+                .takes_value(true)
+                .possible_values(&["text", "json", "jsonl", "csv", "tsv"])
+                .default_value("text"),
+        )
+        .arg(
+            Arg::with_name("header")
+                .long("header")
+                .help("Use the first record of each file as names for JSON objects"),
+        )
+        .arg(
+            Arg::with_name("zero_terminated")
+                .short("z")
+                .long("zero-terminated")
+                .help("Records end with NUL rather than newline"),
         )
         .get_matches();
 
     let files = matches.values_of_lossy("files").unwrap();
-    let delim = matches.value_of("delim").unwrap();
-    let extract: Extract;
 
-    if let Some(v) = matches.values_of_lossy("fields") {
-        extract = Fields(to_position_list(v));
-    } else if let Some(v) = matches.values_of_lossy("bytes") {
-        extract = Bytes(to_position_list(v));
+    let delimiter = match matches.value_of("regex_delim") {
+        Some(pattern) => {
+            // a pattern that matches nothing would split between every byte
+            match Regex::new(pattern) {
+                Ok(re) if !re.is_match(b"") => Delimiter::Regex(re),
+                _ => return Err(format!("Invalid --regex-delim \"{}\"", pattern).into()),
+            }
+        }
+        None if matches.is_present("aligned") => Delimiter::Aligned(vec![]),
+        None if matches.is_present("widths") => {
+            Delimiter::widths(&parse_widths(matches.value_of("widths").unwrap())?)
+        }
+        None => {
+            let delim = matches.value_of("delim").unwrap_or("\t");
+            if delim.is_empty() {
+                return Err(format!("--delim \"{}\" must not be empty", delim).into());
+            }
+            Delimiter::Literal(delim.as_bytes().to_vec())
+        }
+    };
+
+    let extract = if let Some(v) = matches.value_of("fields") {
+        Fields(parse_pos(v)?)
+    } else if let Some(v) = matches.value_of("bytes") {
+        Bytes(parse_pos(v)?)
+    } else if let Some(v) = matches.value_of("chars") {
+        Chars(parse_pos(v)?)
     } else {
-        let v = matches.values_of_lossy("chars").unwrap();
-        extract = Chars(to_position_list(v));
+        return Err("Must have --fields, --bytes, or --chars".into());
+    };
+
+    let mut extractor = Extractor::new(extract)
+        .delimiter(delimiter)
+        .complement(matches.is_present("complement"))
+        .ordered(matches.is_present("ordered"))
+        .graphemes(matches.is_present("graphemes"))
+        .raw_bytes(matches.is_present("raw_bytes"))
+        .only_delimited(matches.is_present("only_delimited"));
+    if let Some(delim) = matches.value_of("output_delim") {
+        extractor = extractor.output_delimiter(delim.as_bytes());
     }
 
     Ok(Config {
         files,
-        // Always guaranteed to exist as delim is defaulted to \t
-        delimiter: delim.as_bytes()[0],
-        extract,
+        extractor,
+        format: match matches.value_of("output_format").unwrap() {
+            "json" => Format::Json,
+            "jsonl" => Format::Jsonl,
+            "csv" => Format::Csv,
+            "tsv" => Format::Tsv,
+            _ => Format::Text,
+        },
+        header: matches.is_present("header"),
+        zero_terminated: matches.is_present("zero_terminated"),
     })
 }
 
-pub fn to_extract_type(s: &str, list: PositionList) -> Extract {
+// the kind of extraction named by its flag letter, "f", "c" or "b"
+pub fn to_extract_type(s: &str, list: PositionList) -> Option<Extract> {
     match s {
-        "f" => Fields(list),
-        "c" => Chars(list),
-        "b" => Bytes(list),
-        _ => todo!(),
+        "f" => Some(Fields(list)),
+        "c" => Some(Chars(list)),
+        "b" => Some(Bytes(list)),
+        _ => None,
     }
 }
 
 pub fn run(c: Config) -> Res<()> {
-    println!("{:#?}", c);
-    Ok(())
+    let stdout = io::stdout();
+    let mut out = BufWriter::new(stdout.lock());
+    let mut printer = Printer::new(&c);
+
+    let res = c
+        .files
+        .iter()
+        .try_for_each(|filename| match open(filename) {
+            Err(e) => {
+                eprintln!("{}: {}", filename, e);
+                Ok(())
+            }
+            Ok(reader) => cut(filename, reader, &c, &mut printer, &mut out),
+        })
+        .and_then(|_| printer.finish(&mut out))
+        .and_then(|_| Ok(out.flush()?));
+
+    match res {
+        // whoever is reading has seen enough, eg head, which isn't an error
+        Err(e) if is_broken_pipe(e.as_ref()) => Ok(()),
+        res => res,
+    }
 }
 
-fn to_position_list(ls: Vec<String>) -> PositionList {
-    // There are 4 possible cases here for raw cli input
-    // 1: x - y -> [x, y]
-    // 2: x, y, z
-    // 3: x
-    // 4: a-b, c-d, e-f
+// streams the records of one input through the printer. output is
+// flushed whenever the input runs dry, so that results show up straight
+// away when sitting in a long running pipeline
+fn cut(
+    filename: &str,
+    mut reader: BufReader<Box<dyn Read>>,
+    c: &Config,
+    printer: &mut Printer,
+    out: &mut impl Write,
+) -> Res<()> {
+    let terminator = if c.zero_terminated { b'\0' } else { b'\n' };
+    let mut record = vec![];
+    let mut is_header = c.header;
+    // aligned columns are learned afresh from the first record of each input
+    let mut aligned = None;
 
-    // This is case 1 | 3
-    if ls.len() == 1 {
-        return vec![to_range(&ls[0])];
-    } else {
-        return ls.iter().map(|s| to_range(s)).collect();
+    loop {
+        if reader.buffer().is_empty() {
+            out.flush()?;
+        }
+        record.clear();
+        match reader.read_until(terminator, &mut record) {
+            Ok(0) => return Ok(()),
+            Ok(_) => {}
+            Err(e) => {
+                eprintln!("{}: {}", filename, e);
+                return Ok(());
+            }
+        }
+        if record.last() == Some(&terminator) {
+            record.pop();
+        }
+
+        if aligned.is_none() && matches!(c.extractor.delimiter, Delimiter::Aligned(_)) {
+            aligned = Some(c.extractor.clone().delimiter(Delimiter::aligned(&record)));
+        }
+        let extractor = aligned.as_ref().unwrap_or(&c.extractor);
+        if let Some(selected) = extractor.extract(&record) {
+            if is_header {
+                printer.header(out, &selected)?;
+            } else {
+                printer.print(out, &selected)?;
+            }
+        }
+        is_header = false;
+    }
+}
+
+fn is_broken_pipe(e: &(dyn Error + 'static)) -> bool {
+    e.downcast_ref::<io::Error>()
+        .is_some_and(|e| e.kind() == io::ErrorKind::BrokenPipe)
+}
+
+// writes the pieces selected from each record in the configured format
+struct Printer {
+    format: Format,
+    // what goes between pieces in text output
+    joiner: Vec<u8>,
+    // what ends each record in text output
+    terminator: u8,
+    // names for the pieces when writing JSON objects
+    keys: Option<Vec<String>>,
+    csv: csv::WriterBuilder,
+    printed: usize,
+}
+
+impl Printer {
+    fn new(c: &Config) -> Self {
+        let mut csv = csv::WriterBuilder::new();
+        csv.delimiter(if c.format == Format::Tsv { b'\t' } else { b',' });
+
+        Printer {
+            format: c.format,
+            joiner: c.extractor.joiner().to_vec(),
+            terminator: if c.zero_terminated { b'\0' } else { b'\n' },
+            keys: None,
+            csv,
+            printed: 0,
+        }
+    }
+
+    // JSON records become objects keyed by the header,
+    // every other format prints it like any other record
+    fn header(&mut self, out: &mut impl Write, pieces: &[Vec<u8>]) -> Res<()> {
+        match self.format {
+            Format::Json | Format::Jsonl => {
+                self.keys = Some(pieces.iter().map(|p| lossy(p)).collect());
+                Ok(())
+            }
+            _ => self.print(out, pieces),
+        }
+    }
+
+    fn print(&mut self, out: &mut impl Write, pieces: &[Vec<u8>]) -> Res<()> {
+        match self.format {
+            Format::Text => {
+                out.write_all(&pieces.join(self.joiner.as_slice()))?;
+                out.write_all(&[self.terminator])?;
+            }
+            Format::Csv | Format::Tsv => {
+                let mut writer = self.csv.from_writer(vec![]);
+                writer.write_record(pieces)?;
+                out.write_all(&writer.into_inner()?)?;
+            }
+            Format::Json => {
+                out.write_all(if self.printed == 0 { b"[\n" } else { b",\n" })?;
+                serde_json::to_writer(&mut *out, &self.to_json(pieces))?;
+            }
+            Format::Jsonl => {
+                serde_json::to_writer(&mut *out, &self.to_json(pieces))?;
+                out.write_all(b"\n")?;
+            }
+        }
+        self.printed += 1;
+        Ok(())
+    }
+
+    // closes off anything left open by the records printed so far
+    fn finish(&mut self, out: &mut impl Write) -> Res<()> {
+        if self.format == Format::Json {
+            out.write_all(if self.printed == 0 { b"[]\n" } else { b"\n]\n" })?;
+        }
+        Ok(())
+    }
+
+    // an array of strings, or an object when there is a header. pieces
+    // beyond the header are keyed by their 1-based position
+    fn to_json(&self, pieces: &[Vec<u8>]) -> Value {
+        match &self.keys {
+            None => pieces.iter().map(|p| Value::String(lossy(p))).collect(),
+            Some(keys) => Value::Object(
+                pieces
+                    .iter()
+                    .enumerate()
+                    .map(|(i, p)| {
+                        let key = keys.get(i).cloned().unwrap_or((i + 1).to_string());
+                        (key, Value::String(lossy(p)))
+                    })
+                    .collect::<Map<_, _>>(),
+            ),
+        }
     }
 }
 
+fn lossy(bytes: &[u8]) -> String {
+    String::from_utf8_lossy(bytes).into_owned()
+}
+
+fn open(filename: &str) -> Res<BufReader<Box<dyn Read>>> {
+    match filename {
+        "-" => Ok(BufReader::new(Box::new(io::stdin()))),
+        _ => Ok(BufReader::new(Box::new(File::open(filename)?))),
+    }
+}
+
+// groups ascending positions into ranges of consecutive ones,
+// eg [0, 1, 2, 5, 6] becomes [0..3, 5..7]
+fn runs(positions: &[usize]) -> Vec<Range<usize>> {
+    let mut runs: Vec<Range<usize>> = vec![];
+    for &i in positions {
+        match runs.last_mut() {
+            Some(last) if last.end == i => last.end = i + 1,
+            _ => runs.push(i..i + 1),
+        }
+    }
+    runs
+}
+
+fn split_literal<'a>(record: &'a [u8], delim: &[u8]) -> Vec<&'a [u8]> {
+    let mut fields = vec![];
+    let mut start = 0;
+    let mut i = 0;
+    while i + delim.len() <= record.len() {
+        if &record[i..i + delim.len()] == delim {
+            fields.push(&record[start..i]);
+            i += delim.len();
+            start = i;
+        } else {
+            i += 1;
+        }
+    }
+    fields.push(&record[start..]);
+    fields
+}
+
+// whether the byte begins a character, rather than continuing a UTF-8 sequence
+fn is_char_start(b: u8) -> bool {
+    b & 0xC0 != 0x80
+}
+
+// cuts a record into columns at the given char offsets, trimming the
+// padding around each one. columns past the end of the record are empty.
+// when snapping, a column that starts part way through a word is moved
+// back to the start of that word, which keeps right aligned values whole
+fn split_columns<'a>(record: &'a [u8], starts: &[usize], snap: bool) -> Vec<&'a [u8]> {
+    if starts.is_empty() {
+        return vec![record];
+    }
+    // the byte offset of every char, plus the end of the record
+    let offsets: Vec<usize> = (0..record.len())
+        .filter(|&i| is_char_start(record[i]))
+        .chain([record.len()])
+        .collect();
+    let at = |col: usize| offsets[col.min(offsets.len() - 1)];
+    let is_space = |col: usize| record.get(at(col)).is_none_or(u8::is_ascii_whitespace);
+
+    let mut bounds: Vec<usize> = vec![];
+    for &start in starts {
+        let prev = bounds.last().copied().unwrap_or(0);
+        let mut col = start;
+        if snap {
+            while col > prev && !is_space(col) && !is_space(col - 1) {
+                col -= 1;
+            }
+            // a single word spanning the whole column can't be helped
+            if col == prev && !bounds.is_empty() {
+                col = start;
+            }
+        }
+        bounds.push(col);
+    }
+
+    bounds
+        .iter()
+        .enumerate()
+        .map(|(i, &start)| {
+            let end = bounds.get(i + 1).map_or(record.len(), |&next| at(next));
+            record[at(start)..end].trim_ascii()
+        })
+        .collect()
+}
+
+// the char offsets where each word of a header begins. the first column
+// always starts at 0 so that right aligned values underneath aren't cut
+fn column_starts(header: &[u8]) -> Vec<usize> {
+    let mut starts = vec![0];
+    let mut seen_word = false;
+    let mut after_space = false;
+    let chars = header.iter().filter(|&&b| is_char_start(b));
+    for (col, &b) in chars.enumerate() {
+        let is_space = b.is_ascii_whitespace();
+        if !is_space {
+            // the first word belongs to the column already started at 0
+            if seen_word && after_space {
+                starts.push(col);
+            }
+            seen_word = true;
+        }
+        after_space = is_space;
+    }
+    starts
+}
+
+// parses a comma separated list of column widths, eg 20,5
+fn parse_widths(list: &str) -> Res<Vec<usize>> {
+    let invalid = || format!("Invalid --widths \"{}\"", list);
+    let widths = list
+        .split(',')
+        .map(|width| match width.parse::<usize>() {
+            Ok(n) if n > 0 && !width.starts_with('+') => Ok(n),
+            _ => Err(invalid()),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    // each column starts where the ones before it add up to
+    widths
+        .iter()
+        .try_fold(0usize, |start, width| start.checked_add(*width))
+        .ok_or_else(invalid)?;
+    Ok(widths)
+}
+
+// converts a 1-based position into a 0-based index, rejecting anything
+// that isn't a plain positive number
+fn to_index(s: &str) -> Option<usize> {
+    if !s.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    s.parse::<usize>().ok().filter(|n| *n > 0).map(|n| n - 1)
+}
+
 // converts a string of either x | x - y into a suitable range
-fn to_range(s: &str) -> Range<usize> {
-    let split: Vec<_> = s.split("-").collect();
-    // exactly 1 item
-    if split.len() == 1 {
-        let lower: usize = split[0].parse().unwrap();
-        return lower - 1..lower;
+fn to_range(s: &str) -> Res<Range<usize>> {
+    let illegal = |v: &str| format!("illegal list value: \"{}\"", v);
+
+    if let Some(lower) = to_index(s) {
+        return Ok(lower..lower + 1);
     }
 
     // 2 items separated by -
-    let lower: usize = split[0].parse().unwrap();
-    let upper: usize = split[1].parse().unwrap();
-    return lower - 1..upper;
+    let split: Vec<_> = s.split('-').collect();
+    if split.len() != 2 || split.iter().any(|v| v.is_empty()) {
+        return Err(illegal(s).into());
+    }
+    let is_number = |v: &str| v.bytes().all(|b| b.is_ascii_digit());
+    let (lower, upper) = match (to_index(split[0]), to_index(split[1])) {
+        (Some(lower), Some(upper)) => (lower, upper),
+        // a well formed range can only be wrong by containing a zero,
+        // in which case we point at that half
+        (None, _) if is_number(split[0]) && is_number(split[1]) => {
+            return Err(illegal(split[0]).into())
+        }
+        (_, None) if is_number(split[0]) && is_number(split[1]) => {
+            return Err(illegal(split[1]).into())
+        }
+        _ => return Err(illegal(s).into()),
+    };
+
+    if lower >= upper {
+        return Err(format!(
+            "First number in range ({}) must be lower than second number ({})",
+            lower + 1,
+            upper + 1
+        )
+        .into());
+    }
+    Ok(lower..upper + 1)
 }
 
-// wrapper over to_position_list
-// does checking first
+// parses a comma separated list of positions and ranges, eg 1,7,3-5
 pub fn parse_pos(range: &str) -> Res<PositionList> {
-    unimplemented!();
+    range.split(',').map(to_range).collect()
 }
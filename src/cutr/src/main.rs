@@ -1,5 +1,3 @@
-use cutr;
-
 fn main() {
     if let Err(e) = cutr::get_args().and_then(cutr::run) {
         eprintln!("{}", e);
@@ -1,7 +1,14 @@
 use assert_cmd::Command;
 use predicates::prelude::*;
 use rand::{distributions::Alphanumeric, Rng};
-use std::fs;
+use std::{
+    fs,
+    io::{BufRead, BufReader, Write},
+    process::{Command as StdCommand, Stdio},
+    sync::mpsc,
+    thread,
+    time::Duration,
+};
 
 type TestResult = Result<(), Box<dyn std::error::Error>>;
 
@@ -9,6 +16,11 @@ const PRG: &str = "cutr";
 const CSV: &str = "tests/inputs/movies1.csv";
 const TSV: &str = "tests/inputs/movies1.tsv";
 const BOOKS: &str = "tests/inputs/books.tsv";
+const BOOKS_TXT: &str = "tests/inputs/books.txt";
+const DCOLON: &str = "tests/inputs/movies1.dcolon";
+const MOVIES2: &str = "tests/inputs/movies2.csv";
+const PS: &str = "tests/inputs/ps.txt";
+const UNICODE: &str = "tests/inputs/unicode.txt";
 
 // --------------------------------------------------
 fn random_string() -> String {
@@ -35,7 +47,7 @@ fn skips_bad_file() -> TestResult {
     let bad = gen_bad_file();
     let expected = format!("{}: .* [(]os error 2[)]", bad);
     Command::cargo_bin(PRG)?
-        .args(&["-f", "1", CSV, &bad, TSV])
+        .args(["-f", "1", CSV, &bad, TSV])
         .assert()
         .success()
         .stderr(predicate::str::is_match(expected)?);
@@ -93,24 +105,43 @@ fn dies_bad_digit_chars() -> TestResult {
 fn dies_empty_delimiter() -> TestResult {
     dies(
         &[CSV, "-f", "1", "-d", ""],
-        "--delim \"\" must be a single byte",
+        "--delim \"\" must not be empty",
     )
 }
 
 // --------------------------------------------------
 #[test]
-fn dies_bad_delimiter() -> TestResult {
+fn dies_bad_regex_delimiter() -> TestResult {
     dies(
-        &[CSV, "-f", "1", "-d", ",,"],
-        "--delim \",,\" must be a single byte",
+        &[CSV, "-f", "1", "--regex-delim", "("],
+        "Invalid --regex-delim \"(\"",
     )
 }
 
+// --------------------------------------------------
+#[test]
+fn dies_empty_regex_delimiter() -> TestResult {
+    dies(
+        &[CSV, "-f", "1", "--regex-delim", r"\s*"],
+        r#"Invalid --regex-delim "\s*""#,
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_delim_and_regex_delim() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args([CSV, "-f", "1", "-d", ",", "--regex-delim", ","])
+        .assert()
+        .failure();
+    Ok(())
+}
+
 // --------------------------------------------------
 #[test]
 fn dies_chars_bytes_fields() -> TestResult {
     Command::cargo_bin(PRG)?
-        .args(&[CSV, "-c", "1", "-f", "1", "-b", "1"])
+        .args([CSV, "-c", "1", "-f", "1", "-b", "1"])
         .assert()
         .failure();
     Ok(())
@@ -120,7 +151,7 @@ fn dies_chars_bytes_fields() -> TestResult {
 #[test]
 fn dies_bytes_fields() -> TestResult {
     Command::cargo_bin(PRG)?
-        .args(&[CSV, "-f", "1", "-b", "1"])
+        .args([CSV, "-f", "1", "-b", "1"])
         .assert()
         .failure();
     Ok(())
@@ -130,7 +161,7 @@ fn dies_bytes_fields() -> TestResult {
 #[test]
 fn dies_chars_fields() -> TestResult {
     Command::cargo_bin(PRG)?
-        .args(&[CSV, "-c", "1", "-f", "1"])
+        .args([CSV, "-c", "1", "-f", "1"])
         .assert()
         .failure();
     Ok(())
@@ -140,7 +171,7 @@ fn dies_chars_fields() -> TestResult {
 #[test]
 fn dies_chars_bytes() -> TestResult {
     Command::cargo_bin(PRG)?
-        .args(&[CSV, "-c", "1", "-b", "1"])
+        .args([CSV, "-c", "1", "-b", "1"])
         .assert()
         .failure();
     Ok(())
@@ -158,6 +189,17 @@ fn run(args: &[&str], expected_file: &str) -> TestResult {
     Ok(())
 }
 
+// --------------------------------------------------
+fn run_raw(args: &[&str], expected_file: &str) -> TestResult {
+    let expected = fs::read(expected_file)?;
+    Command::cargo_bin(PRG)?
+        .args(args)
+        .assert()
+        .success()
+        .stdout(expected);
+    Ok(())
+}
+
 // --------------------------------------------------
 fn run_lossy(args: &[&str], expected_file: &str) -> TestResult {
     let contents = fs::read(expected_file)?;
@@ -260,6 +302,33 @@ fn csv_f1_3() -> TestResult {
     )
 }
 
+// --------------------------------------------------
+#[test]
+fn dcolon_f1_3() -> TestResult {
+    run(
+        &[DCOLON, "-f", "1,3", "-d", "::"],
+        "tests/expected/movies1.dcolon.f1,3.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn regex_delim_f1() -> TestResult {
+    run(
+        &[BOOKS_TXT, "-f", "1", "--regex-delim", " {2,}"],
+        "tests/expected/books.txt.f1.regex.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn regex_delim_csv_f2_3() -> TestResult {
+    run(
+        &[CSV, "-f", "2-3", "--regex-delim", ", *"],
+        "tests/expected/movies1.csv.f2-3.regex.out",
+    )
+}
+
 // --------------------------------------------------
 #[test]
 fn tsv_b1() -> TestResult {
@@ -337,3 +406,342 @@ fn tsv_c1_8() -> TestResult {
 fn repeated_value() -> TestResult {
     run(&[BOOKS, "-c", "1,1"], "tests/expected/books.c1,1.out")
 }
+
+// --------------------------------------------------
+#[test]
+fn dies_only_delimited_chars() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args([TSV, "-c", "1", "-s"])
+        .assert()
+        .failure();
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn tsv_f2_complement() -> TestResult {
+    run(
+        &[TSV, "-f", "2", "--complement"],
+        "tests/expected/movies1.tsv.f2.complement.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn tsv_f1_3_output_delim() -> TestResult {
+    run(
+        &[TSV, "-f", "1,3", "--output-delimiter", " | "],
+        "tests/expected/movies1.tsv.f1,3.odelim.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn tsv_c1_3_5_6_output_delim() -> TestResult {
+    run(
+        &[TSV, "-c", "1-3,5-6", "--output-delimiter", ":"],
+        "tests/expected/movies1.tsv.c1-3,5-6.odelim.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn tsv_b1_2_complement() -> TestResult {
+    run(
+        &[TSV, "-b", "1-2", "--complement"],
+        "tests/expected/movies1.tsv.b1-2.complement.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn only_delimited() -> TestResult {
+    run(
+        &["-f", "1", "-s", BOOKS_TXT, BOOKS],
+        "tests/expected/books.f1.only_delimited.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn not_only_delimited() -> TestResult {
+    run(
+        &["-f", "1", BOOKS_TXT, BOOKS],
+        "tests/expected/books.f1.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_ordered_complement() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args([TSV, "-f", "1", "--ordered", "--complement"])
+        .assert()
+        .failure();
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn tsv_f3_1_ordered() -> TestResult {
+    run(
+        &[TSV, "-f", "3,1", "--ordered"],
+        "tests/expected/movies1.tsv.f3,1.ordered.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn tsv_f3_1_unordered() -> TestResult {
+    run(&[TSV, "-f", "3,1"], "tests/expected/movies1.tsv.f1,3.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn csv_f2_1_2_ordered() -> TestResult {
+    run(
+        &[CSV, "-d", ",", "-f", "2,1-2", "--ordered"],
+        "tests/expected/movies1.csv.f2,1-2.ordered.dcomma.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn repeated_value_ordered() -> TestResult {
+    run(
+        &[BOOKS, "-c", "1,1", "--ordered"],
+        "tests/expected/books.c1,1.ordered.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_graphemes_bytes() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args([UNICODE, "-b", "1", "--graphemes"])
+        .assert()
+        .failure();
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_raw_bytes_chars() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args([UNICODE, "-c", "1", "--raw-bytes"])
+        .assert()
+        .failure();
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn unicode_c1() -> TestResult {
+    run(&[UNICODE, "-c", "1"], "tests/expected/unicode.txt.c1.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn unicode_c1_graphemes() -> TestResult {
+    run(
+        &[UNICODE, "-c", "1", "--graphemes"],
+        "tests/expected/unicode.txt.c1.graphemes.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn unicode_c1_2_graphemes() -> TestResult {
+    run(
+        &[UNICODE, "-c", "1-2", "--graphemes"],
+        "tests/expected/unicode.txt.c1-2.graphemes.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn unicode_b1_4() -> TestResult {
+    run(
+        &[UNICODE, "-b", "1,4"],
+        "tests/expected/unicode.txt.b1,4.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn unicode_b1_4_raw() -> TestResult {
+    run_raw(
+        &[UNICODE, "-b", "1,4", "--raw-bytes"],
+        "tests/expected/unicode.txt.b1,4.raw.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_output_format() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args([TSV, "-f", "1", "--output-format", "xml"])
+        .assert()
+        .failure();
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn books_json_header() -> TestResult {
+    run(
+        &[BOOKS, "-f", "1,3", "--output-format", "json", "--header"],
+        "tests/expected/books.f1,3.header.json.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn books_json() -> TestResult {
+    run(
+        &[BOOKS, "-f", "2-3", "--output-format", "json"],
+        "tests/expected/books.f2-3.json.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn tsv_jsonl() -> TestResult {
+    run(
+        &[TSV, "-f", "1-2", "--output-format", "jsonl"],
+        "tests/expected/movies1.tsv.f1-2.jsonl.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn books_csv() -> TestResult {
+    run(
+        &[BOOKS, "-f", "1,3", "--output-format", "csv"],
+        "tests/expected/books.f1,3.csv.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn csv_tsv_quotes() -> TestResult {
+    run(
+        &[MOVIES2, "-d", ",", "-f", "1", "--output-format", "tsv"],
+        "tests/expected/movies2.csv.f1.tsv.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn empty_json() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-f", "1", "--output-format", "json"])
+        .write_stdin("")
+        .assert()
+        .success()
+        .stdout("[]\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_widths() -> TestResult {
+    dies(
+        &[BOOKS_TXT, "-f", "1", "--widths", "20,0"],
+        "Invalid --widths \"20,0\"",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_overflowing_widths() -> TestResult {
+    dies(
+        &[BOOKS_TXT, "-f", "1", "--widths", "18446744073709551615,2"],
+        "Invalid --widths \"18446744073709551615,2\"",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_aligned_delim() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args([BOOKS_TXT, "-f", "1", "--aligned", "-d", " "])
+        .assert()
+        .failure();
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn books_aligned() -> TestResult {
+    run(
+        &[BOOKS_TXT, "-f", "1,3", "--aligned"],
+        "tests/expected/books.txt.f1,3.aligned.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn ps_aligned() -> TestResult {
+    run(
+        &[PS, "-f", "1,3-4", "--aligned"],
+        "tests/expected/ps.txt.f1,3-4.aligned.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn books_widths() -> TestResult {
+    run(
+        &[BOOKS_TXT, "-f", "2-3", "--widths", "20,5"],
+        "tests/expected/books.txt.f2-3.widths.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn books_widths_rest() -> TestResult {
+    run(
+        &[BOOKS_TXT, "-f", "2", "--widths", "6"],
+        "tests/expected/books.txt.f2.widths6.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn zero_terminated() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-f", "2", "-z"])
+        .write_stdin("a\tb\0c\td\nD\0e\0")
+        .assert()
+        .success()
+        .stdout("b\0d\nD\0e\0");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn streams_records() -> TestResult {
+    let mut child = StdCommand::new(assert_cmd::cargo::cargo_bin(PRG))
+        .args(["-f", "2"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()?;
+    let mut stdin = child.stdin.take().unwrap();
+    let stdout = child.stdout.take().unwrap();
+
+    // the first record must come out while the input is still open
+    stdin.write_all(b"a\tb\n")?;
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let mut line = String::new();
+        let _ = BufReader::new(stdout).read_line(&mut line);
+        let _ = tx.send(line);
+    });
+    let line = rx.recv_timeout(Duration::from_secs(10));
+
+    drop(stdin);
+    child.wait()?;
+    assert_eq!(line?, "b\n");
+    Ok(())
+}
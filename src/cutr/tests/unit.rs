@@ -1,4 +1,5 @@
-use cutr::parse_pos;
+use cutr::{parse_pos, to_extract_type, Delimiter, Extract::*, Extractor, Res};
+use regex::bytes::Regex;
 
 #[test]
 fn test_parse_pos() {
@@ -111,3 +112,71 @@ fn test_parse_pos() {
     assert!(res.is_ok());
     assert_eq!(res.unwrap(), vec![14..15, 18..20]);
 }
+
+#[test]
+fn test_extractor_fields() -> Res<()> {
+    let tsv = Extractor::new(Fields(parse_pos("1,3")?));
+    assert_eq!(tsv.cut(b"a\tb\tc"), Some(b"a\tc".to_vec()));
+    assert_eq!(
+        tsv.extract(b"a\tb\tc"),
+        Some(vec![b"a".to_vec(), b"c".to_vec()])
+    );
+    // no delimiter at all is passed through unless only_delimited
+    assert_eq!(tsv.cut(b"abc"), Some(b"abc".to_vec()));
+    assert_eq!(tsv.clone().only_delimited(true).cut(b"abc"), None);
+
+    let csv = Extractor::new(Fields(parse_pos("3,1")?))
+        .delimiter(Delimiter::Literal(b",".to_vec()))
+        .output_delimiter(b" | ");
+    assert_eq!(csv.cut(b"a,b,c"), Some(b"a | c".to_vec()));
+    assert_eq!(
+        csv.clone().ordered(true).cut(b"a,b,c"),
+        Some(b"c | a".to_vec())
+    );
+    assert_eq!(csv.complement(true).cut(b"a,b,c"), Some(b"b".to_vec()));
+
+    let words =
+        Extractor::new(Fields(parse_pos("2")?)).delimiter(Delimiter::Regex(Regex::new(r"\s+")?));
+    assert_eq!(words.cut(b"one  two   three"), Some(b"two".to_vec()));
+
+    let columns = Extractor::new(Fields(parse_pos("2")?)).delimiter(Delimiter::widths(&[3, 4]));
+    assert_eq!(columns.cut(b"ab cd  ef"), Some(b"cd".to_vec()));
+
+    let aligned =
+        Extractor::new(Fields(parse_pos("1-2")?)).delimiter(Delimiter::aligned(b"  PID CMD"));
+    assert_eq!(aligned.cut(b"12345 cutr"), Some(b"12345\tcutr".to_vec()));
+    Ok(())
+}
+
+#[test]
+fn test_extractor_bytes_chars() -> Res<()> {
+    let bytes = Extractor::new(Bytes(parse_pos("1,3-4")?));
+    assert_eq!(bytes.cut(b"abcde"), Some(b"acd".to_vec()));
+    assert_eq!(
+        bytes.clone().output_delimiter(b":").cut(b"abcde"),
+        Some(b"a:cd".to_vec())
+    );
+    assert_eq!(bytes.cut("é".as_bytes()), Some("�".as_bytes().to_vec()));
+    assert_eq!(bytes.raw_bytes(true).cut("é".as_bytes()), Some(vec![0xC3]));
+
+    let chars = Extractor::new(Chars(parse_pos("1")?));
+    assert_eq!(chars.cut("e\u{301}a".as_bytes()), Some(b"e".to_vec()));
+    assert_eq!(
+        chars.graphemes(true).cut("e\u{301}a".as_bytes()),
+        Some("e\u{301}".as_bytes().to_vec())
+    );
+    Ok(())
+}
+
+#[test]
+fn test_to_extract_type() -> Res<()> {
+    let list = parse_pos("1")?;
+    assert!(matches!(
+        to_extract_type("f", list.clone()),
+        Some(Fields(_))
+    ));
+    assert!(matches!(to_extract_type("c", list.clone()), Some(Chars(_))));
+    assert!(matches!(to_extract_type("b", list.clone()), Some(Bytes(_))));
+    assert!(to_extract_type("x", list).is_none());
+    Ok(())
+}
@@ -0,0 +1,83 @@
+use crate::Res;
+use regex_automata::meta::Regex;
+use regex_automata::util::syntax;
+use regex_automata::Input;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read};
+use std::path::Path;
+
+// enough of a file to recognise its format from the magic bytes
+const SNIFF_LEN: u64 = 8192;
+
+// the most of a line -contains holds in memory at once
+const LINE_MAX: usize = 1024 * 1024;
+const OVERLAP: usize = 4096;
+
+// a pattern for -contains, which may match bytes that aren't UTF-8 as
+// regex::bytes patterns can
+pub fn regex(pattern: &str) -> Res<Regex> {
+    let re = Regex::builder()
+        .syntax(syntax::Config::new().utf8(false))
+        .build(pattern)?;
+    Ok(re)
+}
+
+// whether any line of the file matches, reading no further than the first
+// that does. lines are searched without their line ending, so that $
+// matches before it. lines longer than LINE_MAX, as in files with no
+// newlines at all, are searched in pieces that overlap by OVERLAP bytes.
+// the bytes either side of a piece stay in view, so ^, $ and \b still
+// only match at the real start and end of the line, and only a match
+// longer than OVERLAP can go unseen across a piece's end
+pub fn contains(path: &Path, re: &Regex) -> io::Result<bool> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut line = vec![];
+    // where the search starts in line, past what is kept of the piece
+    // before it
+    let mut start = 0;
+    loop {
+        let room = (LINE_MAX - line.len()) as u64;
+        if reader.by_ref().take(room).read_until(b'\n', &mut line)? == 0 {
+            return Ok(false);
+        }
+        if line.ends_with(b"\n") || reader.fill_buf()?.is_empty() {
+            let text = line.strip_suffix(b"\n").unwrap_or(&line);
+            let text = text.strip_suffix(b"\r").unwrap_or(text);
+            if re.is_match(Input::new(text).range(start..)) {
+                return Ok(true);
+            }
+            line.clear();
+            start = 0;
+        } else {
+            // the last OVERLAP bytes are only looked at from the next piece
+            let end = line.len() - OVERLAP;
+            if re.is_match(Input::new(&line).range(start..end)) {
+                return Ok(true);
+            }
+            line.drain(..end - OVERLAP - 1);
+            start = 1;
+        }
+    }
+}
+
+// the media type of the file going by its first bytes, much as
+// file --mime-type guesses it, falling back to text for UTF-8 without
+// NULs and to a generic binary type for anything else
+pub fn mime_type(path: &Path) -> io::Result<&'static str> {
+    let mut start = vec![];
+    File::open(path)?.take(SNIFF_LEN).read_to_end(&mut start)?;
+    Ok(match infer::get(&start) {
+        Some(kind) => kind.mime_type(),
+        None if start.is_empty() => "inode/x-empty",
+        None if !start.contains(&0) && is_utf8_prefix(&start) => "text/plain",
+        None => "application/octet-stream",
+    })
+}
+
+// the sniffed bytes may end part way through a character
+fn is_utf8_prefix(bytes: &[u8]) -> bool {
+    match std::str::from_utf8(bytes) {
+        Ok(_) => true,
+        Err(e) => e.error_len().is_none(),
+    }
+}
@@ -0,0 +1,142 @@
+use crate::errors::Errors;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::fs::{File, Metadata};
+use std::hash::{Hash, Hasher};
+use std::io::{self, BufRead, BufReader, Read};
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+
+// how much of each file is read before any is read in full
+const PARTIAL: u64 = 4096;
+
+// groups the files with identical contents, largest files first. files
+// are compared by size, then by their first PARTIAL bytes, then by a hash
+// of the whole file and only then byte for byte, so most are never read in
+// full. files no bigger than PARTIAL are read just the once
+pub fn find(
+    mut files: Vec<(PathBuf, Metadata)>,
+    min_size: u64,
+    errors: &Errors,
+) -> Vec<Vec<PathBuf>> {
+    // hard links, or a file reached twice, take no extra space, and only
+    // the first path to each is kept
+    files.sort_by(|a, b| a.0.cmp(&b.0));
+    let mut seen = HashSet::new();
+    let mut by_size: HashMap<u64, Vec<PathBuf>> = HashMap::new();
+    for (path, metadata) in files {
+        if metadata.len() >= min_size && seen.insert((metadata.dev(), metadata.ino())) {
+            by_size.entry(metadata.len()).or_default().push(path);
+        }
+    }
+
+    let mut groups = vec![];
+    for (size, paths) in by_size.into_iter().filter(|(_, paths)| paths.len() > 1) {
+        for paths in split(paths, errors, read_start) {
+            if size <= PARTIAL {
+                groups.push((size, paths));
+                continue;
+            }
+            for paths in split(paths, errors, hash) {
+                let same = compare(paths, errors);
+                groups.extend(same.into_iter().map(|paths| (size, paths)));
+            }
+        }
+    }
+    for (_, paths) in &mut groups {
+        paths.sort();
+    }
+    groups.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(&b.1)));
+    groups.into_iter().map(|(_, paths)| paths).collect()
+}
+
+// regroups paths by a key read from each file, keeping the groups that
+// still have more than one path. files that can't be read are left out
+fn split<K: Hash + Eq>(
+    paths: Vec<PathBuf>,
+    errors: &Errors,
+    key: impl Fn(&Path) -> io::Result<K>,
+) -> Vec<Vec<PathBuf>> {
+    let mut by_key: HashMap<K, Vec<PathBuf>> = HashMap::new();
+    for path in paths {
+        match key(&path) {
+            Ok(key) => by_key.entry(key).or_default().push(path),
+            Err(e) => errors.io(&path, &e),
+        }
+    }
+    by_key
+        .into_values()
+        .filter(|paths| paths.len() > 1)
+        .collect()
+}
+
+// regroups paths that hash the same by comparing each with the first of
+// every group so far, which there is only more than one of when the hash
+// collides. files that can't be read are left out
+fn compare(paths: Vec<PathBuf>, errors: &Errors) -> Vec<Vec<PathBuf>> {
+    let mut groups: Vec<Vec<PathBuf>> = vec![];
+    'paths: for path in paths {
+        for group in &mut groups {
+            while let Some(first) = group.first() {
+                match same_contents(first, &path) {
+                    Ok(true) => {
+                        group.push(path);
+                        continue 'paths;
+                    }
+                    Ok(false) => break,
+                    Err((failed, e)) => {
+                        errors.io(&failed, &e);
+                        if failed == path {
+                            continue 'paths;
+                        }
+                        group.remove(0);
+                    }
+                }
+            }
+        }
+        groups.push(vec![path]);
+    }
+    groups.retain(|paths| paths.len() > 1);
+    groups
+}
+
+fn read_start(path: &Path) -> io::Result<Vec<u8>> {
+    let mut start = vec![];
+    File::open(path)?.take(PARTIAL).read_to_end(&mut start)?;
+    Ok(start)
+}
+
+fn hash(path: &Path) -> io::Result<u64> {
+    let mut reader = File::open(path)?;
+    let mut hasher = DefaultHasher::new();
+    let mut buf = vec![0; 64 * 1024];
+    loop {
+        match reader.read(&mut buf)? {
+            0 => return Ok(hasher.finish()),
+            n => hasher.write(&buf[..n]),
+        }
+    }
+}
+
+// a failure names the file that couldn't be read
+fn same_contents(a: &Path, b: &Path) -> Result<bool, (PathBuf, io::Error)> {
+    let open = |path: &Path| {
+        File::open(path)
+            .map(|file| BufReader::with_capacity(64 * 1024, file))
+            .map_err(|e| (path.to_path_buf(), e))
+    };
+    let (mut x, mut y) = (open(a)?, open(b)?);
+    loop {
+        let p = x.fill_buf().map_err(|e| (a.to_path_buf(), e))?;
+        let q = y.fill_buf().map_err(|e| (b.to_path_buf(), e))?;
+        if p.is_empty() || q.is_empty() {
+            return Ok(p.is_empty() && q.is_empty());
+        }
+        let n = p.len().min(q.len());
+        if p[..n] != q[..n] {
+            return Ok(false);
+        }
+        x.consume(n);
+        y.consume(n);
+    }
+}
@@ -0,0 +1,124 @@
+use std::ffi::OsStr;
+use std::fs::{self, FileType, Metadata};
+use std::io;
+use std::path::{Path, PathBuf};
+
+// an entry found by the walk, kept apart from the walker that found it
+#[derive(Debug, Clone)]
+pub struct Entry {
+    path: PathBuf,
+    depth: usize,
+    file_type: FileType,
+    // whether the path is a symlink that was followed
+    followed: bool,
+}
+
+impl Entry {
+    // walkers descend into a starting path that is a followed symlink but
+    // still report it as a symlink, so follow_root says to look through it
+    fn new(
+        path: PathBuf,
+        depth: usize,
+        mut file_type: FileType,
+        path_is_symlink: bool,
+        follow_root: bool,
+    ) -> Entry {
+        let mut followed = path_is_symlink && !file_type.is_symlink();
+        if follow_root && depth == 0 && file_type.is_symlink() {
+            if let Ok(metadata) = fs::metadata(&path) {
+                file_type = metadata.file_type();
+                followed = true;
+            }
+        }
+        Entry {
+            path,
+            depth,
+            file_type,
+            followed,
+        }
+    }
+
+    pub fn from_walkdir(entry: walkdir::DirEntry, follow_root: bool) -> Entry {
+        let (depth, file_type) = (entry.depth(), entry.file_type());
+        let path_is_symlink = entry.path_is_symlink();
+        Entry::new(
+            entry.into_path(),
+            depth,
+            file_type,
+            path_is_symlink,
+            follow_root,
+        )
+    }
+
+    // only stdin has no file type, and it is never walked
+    pub fn from_ignore(entry: ignore::DirEntry, follow_root: bool) -> Option<Entry> {
+        let (depth, file_type) = (entry.depth(), entry.file_type()?);
+        let path_is_symlink = entry.path_is_symlink();
+        Some(Entry::new(
+            entry.into_path(),
+            depth,
+            file_type,
+            path_is_symlink,
+            follow_root,
+        ))
+    }
+
+    // an entry found outside of a walk, following it if it is a symlink
+    // to something that exists
+    pub fn from_path(path: PathBuf, depth: usize, follow: bool) -> io::Result<Entry> {
+        let mut file_type = fs::symlink_metadata(&path)?.file_type();
+        let mut followed = false;
+        if follow && file_type.is_symlink() {
+            if let Ok(metadata) = fs::metadata(&path) {
+                file_type = metadata.file_type();
+                followed = true;
+            }
+        }
+        Ok(Entry {
+            path,
+            depth,
+            file_type,
+            followed,
+        })
+    }
+
+    // a symlink whose target couldn't be followed is still an entry, as
+    // find -L reports broken links rather than failing on them
+    pub fn broken_link(path: &Path, depth: usize, err: &io::Error) -> Option<Entry> {
+        if err.kind() != io::ErrorKind::NotFound {
+            return None;
+        }
+        let metadata = fs::symlink_metadata(path).ok()?;
+        metadata.file_type().is_symlink().then(|| Entry {
+            path: path.to_path_buf(),
+            depth,
+            file_type: metadata.file_type(),
+            followed: false,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    // the starting paths themselves have no file name, e.g. ".."
+    pub fn file_name(&self) -> &OsStr {
+        self.path.file_name().unwrap_or(self.path.as_os_str())
+    }
+
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    pub fn file_type(&self) -> FileType {
+        self.file_type
+    }
+
+    pub fn metadata(&self) -> io::Result<Metadata> {
+        if self.followed {
+            fs::metadata(&self.path)
+        } else {
+            fs::symlink_metadata(&self.path)
+        }
+    }
+}
@@ -0,0 +1,91 @@
+use std::fmt::Display;
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+// reports what couldn't be read or done as "findr: PATH: reason" while the
+// search carries on, remembering that something failed so that findr can
+// exit non-zero at the end
+#[derive(Debug)]
+pub struct Errors {
+    // --quiet-errors still fails, but says nothing
+    quiet: bool,
+    failed: AtomicBool,
+    // set once whoever reads the output has closed it, eg head, after
+    // which there is nothing left worth doing
+    stopped: AtomicBool,
+}
+
+impl Errors {
+    pub fn new(quiet: bool) -> Errors {
+        Errors {
+            quiet,
+            failed: AtomicBool::new(false),
+            stopped: AtomicBool::new(false),
+        }
+    }
+
+    pub fn report(&self, path: &Path, reason: impl Display) {
+        self.fail();
+        if !self.quiet {
+            eprintln!("findr: {}: {}", path.display(), reason);
+        }
+    }
+
+    pub fn io(&self, path: &Path, err: &io::Error) {
+        self.report(path, describe(err))
+    }
+
+    // for failures that have already had their say, such as a command
+    // that exited non-zero
+    pub fn fail(&self) {
+        self.failed.store(true, Ordering::Relaxed);
+    }
+
+    pub fn failed(&self) -> bool {
+        self.failed.load(Ordering::Relaxed)
+    }
+
+    pub fn stop(&self) {
+        self.stopped.store(true, Ordering::Relaxed);
+    }
+
+    pub fn stopped(&self) -> bool {
+        self.stopped.load(Ordering::Relaxed)
+    }
+}
+
+// an io error without the "(os error 13)" std adds, as find shows them
+pub fn describe(err: &io::Error) -> String {
+    let text = err.to_string();
+    match err.raw_os_error() {
+        Some(code) => text
+            .trim_end_matches(&format!(" (os error {})", code))
+            .to_string(),
+        None => text,
+    }
+}
+
+// writes to stdout, returning whether it could. a closed pipe stops the
+// search without complaint, as whoever is reading has seen enough
+pub fn print(bytes: &[u8], errors: &Errors) -> bool {
+    written(io::stdout().write_all(bytes), errors)
+}
+
+pub fn flush(errors: &Errors) -> bool {
+    written(io::stdout().flush(), errors)
+}
+
+fn written(result: io::Result<()>, errors: &Errors) -> bool {
+    match result {
+        Ok(()) => true,
+        Err(e) if e.kind() == io::ErrorKind::BrokenPipe => {
+            errors.stop();
+            false
+        }
+        Err(e) => {
+            errors.io(Path::new("standard output"), &e);
+            false
+        }
+    }
+}
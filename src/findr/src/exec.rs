@@ -0,0 +1,165 @@
+use crate::errors::Errors;
+use crate::Res;
+use std::ffi::{OsStr, OsString};
+use std::io::{self, Write};
+use std::mem;
+use std::path::Path;
+use std::process::Command;
+use std::sync::Mutex;
+
+// the budget for one batched command line, the same default xargs uses
+const ARG_MAX: usize = 128 * 1024;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum Mode {
+    // -exec CMD ;
+    Each,
+    // -exec CMD {} +
+    Batch,
+    // -ok CMD ;
+    Confirm,
+}
+
+// paths waiting for the next run of a batched command
+#[derive(Debug, Default)]
+struct Batch {
+    paths: Vec<OsString>,
+    len: usize,
+}
+
+#[derive(Debug)]
+pub struct Exec {
+    // for batches this excludes the trailing {}
+    command: Vec<String>,
+    mode: Mode,
+    // shared by every thread of a parallel walk
+    batch: Mutex<Batch>,
+}
+
+// only one -ok prompt may wait on stdin at a time
+static PROMPT: Mutex<()> = Mutex::new(());
+
+// -exec ends at ";", or at "+" right after "{}" for a batch
+pub fn is_terminator(prev: Option<&str>, arg: &str) -> bool {
+    arg == ";" || (arg == "+" && prev == Some("{}"))
+}
+
+impl Exec {
+    // reads the command after -exec or -ok up to its terminator
+    pub fn parse(primary: &str, tokens: &mut impl Iterator<Item = String>) -> Res<Exec> {
+        let mut command: Vec<String> = vec![];
+        let terminator = loop {
+            match tokens.next() {
+                None => {
+                    return Err(format!("Missing \";\" or \"+\" to end {}", primary).into());
+                }
+                Some(arg) if is_terminator(command.last().map(String::as_str), &arg) => {
+                    break arg;
+                }
+                Some(arg) => command.push(arg),
+            }
+        };
+
+        let mode = match (primary, terminator.as_str()) {
+            ("-exec", "+") => {
+                command.pop();
+                Mode::Batch
+            }
+            ("-exec", _) => Mode::Each,
+            (_, ";") => Mode::Confirm,
+            _ => return Err(format!("{} only supports \";\"", primary).into()),
+        };
+        if command.is_empty() {
+            return Err(format!("Missing command for {}", primary).into());
+        }
+        Ok(Exec {
+            command,
+            mode,
+            batch: Mutex::default(),
+        })
+    }
+
+    // runs the command for one match, returning whether it exited zero;
+    // batched paths are only queued, so they always succeed
+    pub fn eval(&self, path: &Path, errors: &Errors) -> bool {
+        match self.mode {
+            Mode::Batch => {
+                let mut batch = self.batch.lock().unwrap();
+                self.queue(&mut batch, path.as_os_str(), errors);
+                true
+            }
+            Mode::Each => run(&self.each_args(path), errors),
+            Mode::Confirm => {
+                let args = self.each_args(path);
+                confirm(&args) && run(&args, errors)
+            }
+        }
+    }
+
+    // runs whatever is left in the batch
+    pub fn finish(&self, errors: &Errors) {
+        self.run_batch(&mut self.batch.lock().unwrap(), errors);
+    }
+
+    fn run_batch(&self, batch: &mut Batch, errors: &Errors) {
+        if !batch.paths.is_empty() {
+            let mut args: Vec<OsString> = self.command.iter().map(OsString::from).collect();
+            args.append(&mut batch.paths);
+            batch.len = 0;
+            // no one match is to blame, but findr still exits non-zero
+            if !run(&args, errors) {
+                errors.fail();
+            }
+        }
+    }
+
+    fn queue(&self, batch: &mut Batch, path: &OsStr, errors: &Errors) {
+        let command_len: usize = self.command.iter().map(|a| arg_len(a.as_ref())).sum();
+        if !batch.paths.is_empty() && command_len + batch.len + arg_len(path) > ARG_MAX {
+            self.run_batch(batch, errors);
+        }
+        batch.len += arg_len(path);
+        batch.paths.push(path.to_os_string());
+    }
+
+    // replaces every {} in the command with the path
+    fn each_args(&self, path: &Path) -> Vec<OsString> {
+        self.command
+            .iter()
+            .map(|arg| {
+                let mut parts = arg.split("{}");
+                let mut out = OsString::from(parts.next().unwrap_or_default());
+                for part in parts {
+                    out.push(path);
+                    out.push(part);
+                }
+                out
+            })
+            .collect()
+    }
+}
+
+// each argument costs its bytes, a NUL and a pointer in argv
+fn arg_len(arg: &OsStr) -> usize {
+    arg.len() + 1 + mem::size_of::<usize>()
+}
+
+fn run(args: &[OsString], errors: &Errors) -> bool {
+    // keep our output ahead of anything the command prints
+    let _ = io::stdout().flush();
+    match Command::new(&args[0]).args(&args[1..]).status() {
+        Ok(status) => status.success(),
+        Err(e) => {
+            errors.io(Path::new(&args[0]), &e);
+            false
+        }
+    }
+}
+
+fn confirm(args: &[OsString]) -> bool {
+    let _prompt = PROMPT.lock().unwrap();
+    let shown: Vec<_> = args.iter().map(|a| a.to_string_lossy()).collect();
+    eprint!("< {} > ? ", shown.join(" "));
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer).is_ok() && answer.trim_start().starts_with(['y', 'Y'])
+}
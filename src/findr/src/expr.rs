@@ -0,0 +1,681 @@
+use crate::content;
+use crate::entry::Entry;
+use crate::errors::{print, Errors};
+use crate::exec::{self, Exec};
+use crate::printf::{self, Format};
+use crate::Res;
+use globset::{GlobBuilder, GlobMatcher};
+use regex::{Regex, RegexBuilder};
+use std::cell::Cell;
+use std::fs::{self, FileType, Metadata};
+use std::io;
+use std::iter::Peekable;
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::{FileTypeExt, MetadataExt};
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::vec::IntoIter;
+
+// primaries that can appear in an expression, with how many arguments each takes
+const PRIMARIES: &[(&str, usize)] = &[
+    ("-name", 1),
+    ("-iname", 1),
+    ("-path", 1),
+    ("-ipath", 1),
+    ("-regex", 1),
+    ("-iregex", 1),
+    ("-type", 1),
+    ("-size", 1),
+    ("-mtime", 1),
+    ("-atime", 1),
+    ("-ctime", 1),
+    ("-newer", 1),
+    ("-empty", 0),
+    ("-perm", 1),
+    ("-user", 1),
+    ("-group", 1),
+    ("-nouser", 0),
+    ("-nogroup", 0),
+    ("-uid", 1),
+    ("-gid", 1),
+    ("-inum", 1),
+    ("-contains", 1),
+    ("-mime", 1),
+    ("-true", 0),
+    ("-false", 0),
+    ("-print", 0),
+    ("-print0", 0),
+    ("-ls", 0),
+    ("-printf", 1),
+    ("-delete", 0),
+    ("-prune", 0),
+];
+
+// find-style options that apply to the whole walk, handed to clap under
+// their long names
+const OPTIONS: &[(&str, &str)] = &[("-xdev", "--xdev")];
+
+const OPERATORS: &[&str] = &["(", ")", "!", "-not", "-a", "-and", "-o", "-or"];
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum EntryType {
+    Dir,
+    File,
+    Link,
+    Block,
+    Char,
+    Fifo,
+    Socket,
+}
+
+impl EntryType {
+    fn matches(self, filetype: FileType) -> bool {
+        match self {
+            EntryType::Dir => filetype.is_dir(),
+            EntryType::File => filetype.is_file(),
+            EntryType::Link => filetype.is_symlink(),
+            EntryType::Block => filetype.is_block_device(),
+            EntryType::Char => filetype.is_char_device(),
+            EntryType::Fifo => filetype.is_fifo(),
+            EntryType::Socket => filetype.is_socket(),
+        }
+    }
+}
+
+// a number given as [+-]N: more than, less than or exactly N
+#[derive(Debug, Clone, Copy)]
+pub enum Cmp {
+    More(u64),
+    Less(u64),
+    Exactly(u64),
+}
+
+impl Cmp {
+    fn matches(self, n: u64) -> bool {
+        match self {
+            Cmp::More(m) => n > m,
+            Cmp::Less(m) => n < m,
+            Cmp::Exactly(m) => n == m,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum TimeField {
+    Accessed,
+    Changed,
+    Modified,
+}
+
+// how -perm compares permission bits: exactly MODE, all of -MODE or any
+// of /MODE
+#[derive(Debug, Clone, Copy)]
+pub enum Perm {
+    Exactly(u32),
+    All(u32),
+    Any(u32),
+}
+
+impl Perm {
+    fn matches(self, mode: u32) -> bool {
+        let mode = mode & 0o7777;
+        match self {
+            Perm::Exactly(bits) => mode == bits,
+            Perm::All(bits) => mode & bits == bits,
+            // find treats /000 as matching everything
+            Perm::Any(bits) => bits == 0 || mode & bits != 0,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum Expr {
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Bool(bool),
+    // --name, a regex searched for in the file name
+    NameRegex(Regex),
+    // -name and -iname, a glob matching the whole file name
+    Name(GlobMatcher),
+    // -path and -ipath, a glob matching the whole path
+    Path(GlobMatcher),
+    // -regex and -iregex, anchored at both ends of the path
+    Regex(Regex),
+    Type(EntryType),
+    // size in units of the given number of bytes, rounded up as find does
+    Size(Cmp, u64),
+    // whole days since the given timestamp
+    Time(TimeField, Cmp),
+    Newer(SystemTime),
+    Empty,
+    Perm(Perm),
+    // -uid and -user
+    Uid(Cmp),
+    // -gid and -group
+    Gid(Cmp),
+    NoUser,
+    NoGroup,
+    Inum(Cmp),
+    // -contains, a regex searched for in each line of a regular file
+    Contains(regex_automata::meta::Regex),
+    // -mime, a glob matching the sniffed media type of a regular file
+    Mime(GlobMatcher),
+    Print,
+    Print0,
+    Printf(Format),
+    Ls,
+    Exec(Exec),
+    Delete,
+    Prune,
+}
+
+// what an expression is evaluated against besides the entry, made
+// afresh for each entry so that a parallel walk can share the rest
+#[derive(Debug)]
+pub struct Env<'a> {
+    // times are measured from when the search starts, as find does
+    pub now: SystemTime,
+    // -delete only reports what it would remove
+    pub dry_run: bool,
+    // -print writes a JSON object per match instead of the path
+    pub json: bool,
+    // set by -prune when the current directory shouldn't be descended into
+    pub prune: Cell<bool>,
+    pub errors: &'a Errors,
+}
+
+impl Expr {
+    // joins expressions with -and, matching everything when there are none
+    pub fn all(exprs: impl IntoIterator<Item = Expr>) -> Expr {
+        exprs
+            .into_iter()
+            .reduce(|a, b| Expr::And(Box::new(a), Box::new(b)))
+            .unwrap_or(Expr::Bool(true))
+    }
+
+    // joins expressions with -or, matching nothing when there are none
+    pub fn any(exprs: impl IntoIterator<Item = Expr>) -> Expr {
+        exprs
+            .into_iter()
+            .reduce(|a, b| Expr::Or(Box::new(a), Box::new(b)))
+            .unwrap_or(Expr::Bool(false))
+    }
+
+    // builds the test for a primary or the option spelling of it, e.g.
+    // "-size" or "--size", reporting a bad value against that spelling
+    pub fn test(flag: &str, value: &str) -> Res<Expr> {
+        let invalid = || format!("Invalid {} \"{}\"", flag, value);
+        let glob = |case_insensitive| {
+            GlobBuilder::new(value)
+                .case_insensitive(case_insensitive)
+                .build()
+                .map(|glob| glob.compile_matcher())
+                .map_err(|_| invalid())
+        };
+        let regex = |case_insensitive| {
+            RegexBuilder::new(&format!("^(?:{})$", value))
+                .case_insensitive(case_insensitive)
+                .build()
+                .map_err(|_| invalid())
+        };
+        let expr = match flag.trim_start_matches('-') {
+            // the --name option predates the expression and stays a regex
+            "name" if flag == "--name" => {
+                Expr::NameRegex(Regex::new(value).map_err(|_| invalid())?)
+            }
+            "name" => Expr::Name(glob(false)?),
+            "iname" => Expr::Name(glob(true)?),
+            "path" => Expr::Path(glob(false)?),
+            "ipath" => Expr::Path(glob(true)?),
+            "regex" => Expr::Regex(regex(false)?),
+            "iregex" => Expr::Regex(regex(true)?),
+            "type" => Expr::Type(match value {
+                "d" => EntryType::Dir,
+                "f" => EntryType::File,
+                "l" => EntryType::Link,
+                "b" => EntryType::Block,
+                "c" => EntryType::Char,
+                "p" => EntryType::Fifo,
+                "s" => EntryType::Socket,
+                _ => return Err(invalid().into()),
+            }),
+            "size" => {
+                let (number, unit) = split_unit(value);
+                Expr::Size(parse_cmp(number).ok_or_else(invalid)?, unit)
+            }
+            "mtime" => Expr::Time(TimeField::Modified, parse_cmp(value).ok_or_else(invalid)?),
+            "atime" => Expr::Time(TimeField::Accessed, parse_cmp(value).ok_or_else(invalid)?),
+            "ctime" => Expr::Time(TimeField::Changed, parse_cmp(value).ok_or_else(invalid)?),
+            "newer" => {
+                let metadata = fs::metadata(value).map_err(|e| format!("{}: {}", value, e))?;
+                Expr::Newer(timestamp(&metadata, TimeField::Modified))
+            }
+            "perm" => {
+                let perm = match value.chars().next() {
+                    Some('-') => Perm::All(parse_mode(&value[1..]).ok_or_else(invalid)?),
+                    Some('/') => Perm::Any(parse_mode(&value[1..]).ok_or_else(invalid)?),
+                    _ => Perm::Exactly(parse_mode(value).ok_or_else(invalid)?),
+                };
+                Expr::Perm(perm)
+            }
+            // names are looked up once, and numeric ids are taken as they are
+            "user" => Expr::Uid(Cmp::Exactly(
+                match uzers::get_user_by_name(value) {
+                    Some(user) => user.uid(),
+                    None => value
+                        .parse()
+                        .map_err(|_| format!("Unknown user \"{}\"", value))?,
+                }
+                .into(),
+            )),
+            "group" => Expr::Gid(Cmp::Exactly(
+                match uzers::get_group_by_name(value) {
+                    Some(group) => group.gid(),
+                    None => value
+                        .parse()
+                        .map_err(|_| format!("Unknown group \"{}\"", value))?,
+                }
+                .into(),
+            )),
+            "uid" => Expr::Uid(parse_cmp(value).ok_or_else(invalid)?),
+            "gid" => Expr::Gid(parse_cmp(value).ok_or_else(invalid)?),
+            "inum" => Expr::Inum(parse_cmp(value).ok_or_else(invalid)?),
+            "contains" => Expr::Contains(content::regex(value).map_err(|_| invalid())?),
+            "mime" => Expr::Mime(glob(true)?),
+            _ => return Err(format!("Unknown primary \"{}\"", flag).into()),
+        };
+        Ok(expr)
+    }
+
+    // whether any part of the expression satisfies the predicate
+    pub fn contains(&self, pred: &impl Fn(&Expr) -> bool) -> bool {
+        pred(self)
+            || match self {
+                Expr::And(a, b) | Expr::Or(a, b) => a.contains(pred) || b.contains(pred),
+                Expr::Not(a) => a.contains(pred),
+                _ => false,
+            }
+    }
+
+    // whether the expression does something with a match besides matching
+    pub fn has_action(&self) -> bool {
+        self.contains(&|e| {
+            matches!(
+                e,
+                Expr::Print
+                    | Expr::Print0
+                    | Expr::Printf(_)
+                    | Expr::Ls
+                    | Expr::Exec(_)
+                    | Expr::Delete
+            )
+        })
+    }
+
+    // whether the expression looks at entries at all, rather than only
+    // combining constants and actions
+    pub fn has_test(&self) -> bool {
+        self.contains(&|e| {
+            !matches!(
+                e,
+                Expr::And(..)
+                    | Expr::Or(..)
+                    | Expr::Not(_)
+                    | Expr::Bool(_)
+                    | Expr::Print
+                    | Expr::Print0
+                    | Expr::Printf(_)
+                    | Expr::Ls
+                    | Expr::Delete
+                    | Expr::Prune
+            )
+        })
+    }
+
+    // evaluates left to right, skipping the right side of -and and -or
+    // once the left side decides the result
+    pub fn eval(&self, entry: &Entry, env: &Env) -> bool {
+        match self {
+            Expr::And(a, b) => a.eval(entry, env) && b.eval(entry, env),
+            Expr::Or(a, b) => a.eval(entry, env) || b.eval(entry, env),
+            Expr::Not(a) => !a.eval(entry, env),
+            Expr::Bool(b) => *b,
+            Expr::NameRegex(re) => re.is_match(&entry.file_name().to_string_lossy()),
+            Expr::Name(glob) => glob.is_match(entry.file_name()),
+            Expr::Path(glob) => glob.is_match(entry.path()),
+            Expr::Regex(re) => re.is_match(&entry.path().to_string_lossy()),
+            Expr::Type(t) => t.matches(entry.file_type()),
+            Expr::Contains(re) => {
+                entry.file_type().is_file()
+                    && report(entry, env, content::contains(entry.path(), re)).unwrap_or(false)
+            }
+            Expr::Mime(glob) => {
+                entry.file_type().is_file()
+                    && report(entry, env, content::mime_type(entry.path()))
+                        .is_some_and(|mime| glob.is_match(mime))
+            }
+            Expr::Print if env.json => print(
+                &format!("{}\n", printf::json(entry)).into_bytes(),
+                env.errors,
+            ),
+            Expr::Print => print(&line(entry.path(), b'\n'), env.errors),
+            Expr::Print0 => print(&line(entry.path(), b'\0'), env.errors),
+            Expr::Printf(format) => print(&format.render(entry), env.errors),
+            Expr::Ls => match entry.metadata() {
+                Ok(metadata) => print(&printf::long_listing(entry, &metadata, env.now), env.errors),
+                Err(e) => {
+                    env.errors.io(entry.path(), &e);
+                    false
+                }
+            },
+            Expr::Exec(exec) => exec.eval(entry.path(), env.errors),
+            Expr::Delete => delete(entry, env),
+            Expr::Prune => {
+                env.prune.set(true);
+                true
+            }
+            _ => report(entry, env, entry.metadata())
+                .is_some_and(|metadata| self.eval_metadata(entry, &metadata, env)),
+        }
+    }
+
+    fn eval_metadata(&self, entry: &Entry, metadata: &Metadata, env: &Env) -> bool {
+        match self {
+            Expr::Size(cmp, unit) => cmp.matches(metadata.len().div_ceil(*unit)),
+            Expr::Time(field, cmp) => {
+                let days = env
+                    .now
+                    .duration_since(timestamp(metadata, *field))
+                    .map(|age| age.as_secs() / 86400)
+                    .unwrap_or(0);
+                cmp.matches(days)
+            }
+            Expr::Newer(time) => timestamp(metadata, TimeField::Modified) > *time,
+            Expr::Empty => {
+                if metadata.is_dir() {
+                    report(entry, env, fs::read_dir(entry.path()))
+                        .is_some_and(|mut dir| dir.next().is_none())
+                } else {
+                    metadata.is_file() && metadata.len() == 0
+                }
+            }
+            Expr::Perm(perm) => perm.matches(metadata.mode()),
+            Expr::Uid(cmp) => cmp.matches(metadata.uid().into()),
+            Expr::Gid(cmp) => cmp.matches(metadata.gid().into()),
+            Expr::NoUser => uzers::get_user_by_uid(metadata.uid()).is_none(),
+            Expr::NoGroup => uzers::get_group_by_gid(metadata.gid()).is_none(),
+            Expr::Inum(cmp) => cmp.matches(metadata.ino()),
+            _ => unreachable!("not a metadata test"),
+        }
+    }
+
+    // runs any batched commands still waiting once the walk, or a round of
+    // --watch changes, is over
+    pub fn finish(&self, errors: &Errors) {
+        match self {
+            Expr::And(a, b) | Expr::Or(a, b) => {
+                a.finish(errors);
+                b.finish(errors);
+            }
+            Expr::Not(a) => a.finish(errors),
+            Expr::Exec(exec) => exec.finish(errors),
+            _ => {}
+        }
+    }
+}
+
+// a path as it is, which needn't be UTF-8, followed by the terminator
+pub fn line(path: &Path, terminator: u8) -> Vec<u8> {
+    let mut out = path.as_os_str().as_bytes().to_vec();
+    out.push(terminator);
+    out
+}
+
+// files that can't be read match nothing, and are reported
+fn report<T>(entry: &Entry, env: &Env, result: io::Result<T>) -> Option<T> {
+    result.map_err(|e| env.errors.io(entry.path(), &e)).ok()
+}
+
+// removes a file or an empty directory, relying on the walk visiting a
+// directory's contents before the directory itself
+fn delete(entry: &Entry, env: &Env) -> bool {
+    let path = entry.path();
+    // the starting point "." can't be removed, so leave it be as find does
+    if path == Path::new(".") {
+        return true;
+    }
+    if env.dry_run {
+        return print(&line(path, b'\n'), env.errors);
+    }
+    let removed = if entry.file_type().is_dir() {
+        fs::remove_dir(path)
+    } else {
+        fs::remove_file(path)
+    };
+    match removed {
+        Ok(()) => true,
+        Err(e) => {
+            env.errors.io(path, &e);
+            false
+        }
+    }
+}
+
+fn timestamp(metadata: &Metadata, field: TimeField) -> SystemTime {
+    // std has no portable ctime, so build all three from the raw stat fields
+    let (secs, nsecs) = match field {
+        TimeField::Accessed => (metadata.atime(), metadata.atime_nsec()),
+        TimeField::Changed => (metadata.ctime(), metadata.ctime_nsec()),
+        TimeField::Modified => (metadata.mtime(), metadata.mtime_nsec()),
+    };
+    let offset = Duration::new(secs.unsigned_abs(), nsecs as u32);
+    if secs < 0 {
+        UNIX_EPOCH - offset
+    } else {
+        UNIX_EPOCH + offset
+    }
+}
+
+// reads an octal mode such as 644, or a symbolic one such as u+w,go=r
+// applied to no permissions at all
+fn parse_mode(value: &str) -> Option<u32> {
+    if !value.is_empty() && value.bytes().all(|b| (b'0'..=b'7').contains(&b)) {
+        return u32::from_str_radix(value, 8)
+            .ok()
+            .filter(|mode| *mode <= 0o7777);
+    }
+
+    let mut mode = 0;
+    for clause in value.split(',') {
+        let op_at = clause.find(['+', '-', '='])?;
+        let (who, rest) = clause.split_at(op_at);
+        let mut mask = 0;
+        for c in who.chars() {
+            mask |= match c {
+                'u' => 0o4700,
+                'g' => 0o2070,
+                'o' => 0o1007,
+                'a' => 0o7777,
+                _ => return None,
+            };
+        }
+        if mask == 0 {
+            mask = 0o7777;
+        }
+        let mut perms = rest.chars();
+        let op = perms.next()?;
+        let mut bits = 0;
+        for c in perms {
+            bits |= match c {
+                'r' => 0o444,
+                'w' => 0o222,
+                'x' => 0o111,
+                's' => 0o6000,
+                't' => 0o1000,
+                _ => return None,
+            };
+        }
+        match op {
+            '+' => mode |= bits & mask,
+            '-' => mode &= !(bits & mask),
+            _ => mode = (mode & !mask) | (bits & mask),
+        }
+    }
+    Some(mode)
+}
+
+// splits a k, M or G suffix off a size, which is otherwise in bytes
+pub fn split_unit(value: &str) -> (&str, u64) {
+    match value.char_indices().last() {
+        Some((i, 'k')) => (&value[..i], 1 << 10),
+        Some((i, 'M')) => (&value[..i], 1 << 20),
+        Some((i, 'G')) => (&value[..i], 1 << 30),
+        _ => (value, 1),
+    }
+}
+
+fn parse_cmp(value: &str) -> Option<Cmp> {
+    let (make, digits): (fn(u64) -> Cmp, &str) = if let Some(rest) = value.strip_prefix('+') {
+        (Cmp::More, rest)
+    } else if let Some(rest) = value.strip_prefix('-') {
+        (Cmp::Less, rest)
+    } else {
+        (Cmp::Exactly, value)
+    };
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    digits.parse().ok().map(make)
+}
+
+// separates the find-style expression from the arguments clap understands;
+// the expression may be interleaved with options and paths, but the value
+// of an option that takes one is never read as part of it
+pub fn split_args(
+    args: impl IntoIterator<Item = String>,
+    value_options: &[&str],
+) -> (Vec<String>, Vec<String>) {
+    let mut options = vec![];
+    let mut expr = vec![];
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        if value_options.contains(&arg.as_str()) {
+            options.push(arg);
+            options.extend(args.next());
+        } else if let Some((_, long)) = OPTIONS.iter().find(|(o, _)| *o == arg) {
+            options.push(long.to_string());
+        } else if OPERATORS.contains(&arg.as_str()) {
+            expr.push(arg);
+        } else if arg == "-exec" || arg == "-ok" {
+            expr.push(arg);
+            let start = expr.len();
+            for arg in args.by_ref() {
+                let prev = expr[start..].last().map(String::as_str);
+                let done = exec::is_terminator(prev, &arg);
+                expr.push(arg);
+                if done {
+                    break;
+                }
+            }
+        } else if let Some((_, arity)) = PRIMARIES.iter().find(|(p, _)| *p == arg) {
+            expr.push(arg);
+            expr.extend(args.by_ref().take(*arity));
+        } else {
+            options.push(arg);
+        }
+    }
+    (options, expr)
+}
+
+type Tokens = Peekable<IntoIter<String>>;
+
+// parses the expression with find's precedence: ! binds tightest, then
+// -and (also implied by two tests in a row), then -or
+pub fn parse(tokens: Vec<String>) -> Res<Option<Expr>> {
+    if tokens.is_empty() {
+        return Ok(None);
+    }
+    let mut tokens = tokens.into_iter().peekable();
+    let expr = parse_or(&mut tokens)?;
+    match tokens.next() {
+        None => Ok(Some(expr)),
+        Some(token) => Err(format!("Unexpected \"{}\" in expression", token).into()),
+    }
+}
+
+fn parse_or(tokens: &mut Tokens) -> Res<Expr> {
+    let mut expr = parse_and(tokens)?;
+    while let Some(op) = tokens.next_if(|t| t == "-o" || t == "-or") {
+        expect_operand(tokens, &op)?;
+        let rhs = parse_and(tokens)?;
+        expr = Expr::Or(Box::new(expr), Box::new(rhs));
+    }
+    Ok(expr)
+}
+
+fn parse_and(tokens: &mut Tokens) -> Res<Expr> {
+    let mut expr = parse_not(tokens)?;
+    loop {
+        let rhs = match tokens.peek().map(String::as_str) {
+            None | Some(")" | "-o" | "-or") => break,
+            Some("-a" | "-and") => {
+                let op = tokens.next().unwrap_or_default();
+                expect_operand(tokens, &op)?;
+                parse_not(tokens)?
+            }
+            Some(_) => parse_not(tokens)?,
+        };
+        expr = Expr::And(Box::new(expr), Box::new(rhs));
+    }
+    Ok(expr)
+}
+
+fn parse_not(tokens: &mut Tokens) -> Res<Expr> {
+    let token = tokens.next().ok_or("Incomplete expression")?;
+    match token.as_str() {
+        "!" | "-not" => {
+            expect_operand(tokens, &token)?;
+            let expr = parse_not(tokens)?;
+            Ok(Expr::Not(Box::new(expr)))
+        }
+        "(" => {
+            expect_operand(tokens, &token)?;
+            let expr = parse_or(tokens)?;
+            match tokens.next().as_deref() {
+                Some(")") => Ok(expr),
+                _ => Err("Missing \")\" in expression".into()),
+            }
+        }
+        "-true" => Ok(Expr::Bool(true)),
+        "-false" => Ok(Expr::Bool(false)),
+        "-empty" => Ok(Expr::Empty),
+        "-nouser" => Ok(Expr::NoUser),
+        "-nogroup" => Ok(Expr::NoGroup),
+        "-print" => Ok(Expr::Print),
+        "-print0" => Ok(Expr::Print0),
+        "-ls" => Ok(Expr::Ls),
+        "-printf" => {
+            let format = tokens.next().ok_or("Missing argument to -printf")?;
+            Ok(Expr::Printf(Format::parse(&format)?))
+        }
+        "-delete" => Ok(Expr::Delete),
+        "-prune" => Ok(Expr::Prune),
+        "-exec" | "-ok" => Ok(Expr::Exec(Exec::parse(&token, tokens)?)),
+        _ if PRIMARIES.iter().any(|(p, _)| *p == token) => {
+            let value = tokens
+                .next()
+                .ok_or_else(|| format!("Missing argument to {}", token))?;
+            Expr::test(&token, &value)
+        }
+        _ => Err(format!("Expected a test before \"{}\"", token).into()),
+    }
+}
+
+// an operator left without anything to apply it to
+fn expect_operand(tokens: &mut Tokens, op: &str) -> Res<()> {
+    match tokens.peek() {
+        None => Err(format!("Expected a test after \"{}\"", op).into()),
+        Some(_) => Ok(()),
+    }
+}
@@ -1,40 +1,100 @@
-use crate::EntryType::*;
+use crate::entry::Entry;
+use crate::errors::{print, Errors};
+use crate::expr::{Env, Expr};
+use crate::walk::{Follow, SortKey, WalkOptions};
+use crate::watch::Watcher;
 use clap::{App, Arg};
-use regex::Regex;
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use std::cell::Cell;
 use std::error::Error;
-use walkdir::WalkDir;
+use std::mem;
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+mod content;
+mod dupes;
+mod entry;
+mod errors;
+mod exec;
+mod expr;
+mod printf;
+mod walk;
+mod watch;
 
 type Res<T> = Result<T, Box<dyn Error>>;
 
-#[derive(Debug, Eq, PartialEq)]
-enum EntryType {
-    Dir,
-    File,
-    Link,
-}
+// options whose value must not be mistaken for part of the expression
+const VALUE_OPTIONS: &[&str] = &[
+    "-n",
+    "--name",
+    "-t",
+    "--type",
+    "--min-depth",
+    "--max-depth",
+    "--size",
+    "--mtime",
+    "--atime",
+    "--ctime",
+    "--newer",
+    "--exclude-dir",
+    "-j",
+    "--threads",
+    "--sort",
+    "--dup-min-size",
+];
 
 #[derive(Debug)]
 pub struct Config {
     paths: Vec<String>,
-    names: Vec<Regex>,
-    entry_types: Vec<EntryType>,
-}
-
-fn is_match(regs: &Vec<Regex>, s: &str) -> bool {
-    regs.len() == 0 || regs.iter().any(|r| r.is_match(s))
+    expr: Expr,
+    dry_run: bool,
+    json: bool,
+    // the smallest size of file to look for duplicates of
+    duplicates: Option<u64>,
+    quiet_errors: bool,
+    watch: bool,
+    walk: WalkOptions,
 }
 
 pub fn get_args() -> Res<Config> {
+    let (args, expr) = expr::split_args(std::env::args(), VALUE_OPTIONS);
     let matches = App::new("findr")
         .version("0.1.0")
         .about("Rust find")
+        .after_help(
+            "EXPRESSION:
+    Options may be followed or interleaved with a find-style expression:
+        -name GLOB  -iname GLOB  -path GLOB  -ipath GLOB  -regex REGEX
+        -iregex REGEX  -type b|c|d|f|l|p|s  -size [+-]N[kMG]
+        -mtime|-atime|-ctime [+-]N  -newer FILE  -empty  -perm [-/]MODE
+        -user NAME  -group NAME  -nouser  -nogroup  -uid|-gid|-inum [+-]N
+        -contains REGEX  -mime TYPE  -true  -false
+    combined with ( EXPR ), ! EXPR or -not EXPR, EXPR [-a|-and] EXPR and
+    EXPR -o|-or EXPR. The options above are all ANDed with the expression.
+    Matches are printed unless the expression has one of the actions
+        -print  -exec CMD [ARG]... ;  -exec CMD [ARG]... {} +  -ok CMD [ARG]... ;
+        -delete  -print0  -printf FORMAT  -ls  -prune
+    where {} is replaced by the path and -exec and -ok are true when CMD
+    exits zero. FORMAT takes the escapes \\n \\t \\r \\0 \\\\ and the directives
+        %p path  %f name  %h parent  %s size  %m octal mode  %M ls-style mode
+        %u owner  %T@ mtime in seconds  %y type  %d depth  %% a literal %
+    -delete visits directories after their contents and needs a test to
+    narrow it down unless --force is given. -prune is true and stops the
+    search from descending into a matched directory. -ls writes a line as
+    ls -l would, and --json makes -print write an object of path, type,
+    size, mode, mtime and depth instead. -contains and -mime only match
+    regular files, by a line of their contents or by a type such as
+    image/png or image/* sniffed from their first bytes.",
+        )
         .arg(Arg::with_name("paths").multiple(true).default_value("."))
         .arg(
             Arg::with_name("name")
                 .takes_value(true)
                 .multiple(true)
                 .short("n")
-                .long("name"),
+                .long("name")
+                .value_name("REGEX")
+                .help("Search file names for REGEX, unlike the glob -name"),
         )
         .arg(
             Arg::with_name("type")
@@ -43,69 +103,344 @@ pub fn get_args() -> Res<Config> {
                 .short("t")
                 .long("type")
                 .value_name("TYPE")
-                .possible_values(&["d", "l", "f"]),
+                .possible_values(&["b", "c", "d", "f", "l", "p", "s"]),
+        )
+        .arg(
+            Arg::with_name("min_depth")
+                .takes_value(true)
+                .long("min-depth")
+                .value_name("DEPTH")
+                .help("Ignore entries less than DEPTH levels below each path"),
+        )
+        .arg(
+            Arg::with_name("max_depth")
+                .takes_value(true)
+                .long("max-depth")
+                .value_name("DEPTH")
+                .help("Descend at most DEPTH levels below each path"),
+        )
+        .arg(
+            Arg::with_name("size")
+                .takes_value(true)
+                .long("size")
+                .value_name("[+-]N[kMG]")
+                .allow_hyphen_values(true)
+                .help("Size is more than (+), less than (-) or exactly N"),
+        )
+        .arg(
+            Arg::with_name("mtime")
+                .takes_value(true)
+                .long("mtime")
+                .value_name("[+-]N")
+                .allow_hyphen_values(true)
+                .help("Last modified N days ago"),
+        )
+        .arg(
+            Arg::with_name("atime")
+                .takes_value(true)
+                .long("atime")
+                .value_name("[+-]N")
+                .allow_hyphen_values(true)
+                .help("Last accessed N days ago"),
+        )
+        .arg(
+            Arg::with_name("ctime")
+                .takes_value(true)
+                .long("ctime")
+                .value_name("[+-]N")
+                .allow_hyphen_values(true)
+                .help("Status last changed N days ago"),
+        )
+        .arg(
+            Arg::with_name("newer")
+                .takes_value(true)
+                .long("newer")
+                .value_name("FILE")
+                .help("Modified more recently than FILE"),
+        )
+        .arg(
+            Arg::with_name("empty")
+                .long("empty")
+                .help("Empty file or directory"),
+        )
+        .arg(
+            Arg::with_name("force")
+                .long("force")
+                .help("Allow -delete without any test to narrow it down"),
+        )
+        .arg(
+            Arg::with_name("dry_run")
+                .long("dry-run")
+                .help("Print what -delete would remove instead of removing it"),
+        )
+        .arg(
+            Arg::with_name("follow")
+                .short("L")
+                .overrides_with("follow_roots")
+                .help("Follow symlinks, reporting any that loop back to a parent"),
+        )
+        .arg(
+            Arg::with_name("follow_roots")
+                .short("H")
+                .overrides_with("follow")
+                .help("Follow symlinks given as paths but not those found below them"),
+        )
+        .arg(
+            Arg::with_name("xdev")
+                .long("xdev")
+                .help("Don't descend into other filesystems [also: -xdev]"),
+        )
+        .arg(
+            Arg::with_name("exclude_dir")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .long("exclude-dir")
+                .value_name("GLOB")
+                .help("Skip directories named GLOB and everything in them"),
+        )
+        .arg(
+            Arg::with_name("respect_ignore")
+                .long("respect-ignore")
+                .help("Skip hidden and ignored files as git and fd do"),
         )
-        .get_matches();
+        .arg(
+            Arg::with_name("threads")
+                .takes_value(true)
+                .short("j")
+                .long("threads")
+                .value_name("NUM")
+                .help("Walk with NUM threads, in no particular order [default: 1]"),
+        )
+        .arg(
+            Arg::with_name("sort")
+                .takes_value(true)
+                .long("sort")
+                .value_name("KEY")
+                .possible_values(&["name", "size", "mtime"])
+                .help("Visit entries in order of KEY, smallest first, or by name within each directory"),
+        )
+        .arg(
+            Arg::with_name("json")
+                .long("json")
+                .help("Print each match as a line of JSON"),
+        )
+        .arg(
+            Arg::with_name("duplicates")
+                .long("duplicates")
+                .help("Print groups of matched files with the same contents"),
+        )
+        .arg(
+            Arg::with_name("dup_min_size")
+                .takes_value(true)
+                .long("dup-min-size")
+                .value_name("SIZE")
+                .requires("duplicates")
+                .help("Only report duplicates of at least SIZE[kMG] bytes [default: 1]"),
+        )
+        .arg(
+            Arg::with_name("quiet_errors")
+                .long("quiet-errors")
+                .help("Don't report unreadable paths, but still exit non-zero"),
+        )
+        .arg(
+            Arg::with_name("watch")
+                .long("watch")
+                .conflicts_with_all(&["respect_ignore", "duplicates"])
+                .help("Keep watching the paths, testing what is created, written or renamed"),
+        )
+        .get_matches_from(args);
 
     let paths = matches.values_of_lossy("paths").unwrap();
-    let names: Result<Vec<_>, String> = matches
-        .values_of_lossy("name")
-        .unwrap_or(Vec::new())
-        .iter()
-        .map(|s| Regex::new(s).map_err(|_| format!("Invalid --name \"{}\"", s)))
-        .collect();
 
-    // NOTE: This is defaulted to file
-    let entry_types = matches
-        .values_of_lossy("type")
-        .unwrap_or(vec!["d".to_string(), "l".to_string(), "f".to_string()])
-        .iter()
-        .map(|t| {
-            let s: &str = t;
-            match s {
-                "d" => Dir,
-                "l" => Link,
-                "f" => File,
-                _ => panic!("impossible!"),
-            }
+    // each repeated option is an alternative, and the options are ANDed
+    // together and with the expression
+    let mut exprs = vec![];
+    for name in ["name", "type"] {
+        if let Some(values) = matches.values_of(name) {
+            let flag = format!("--{}", name);
+            let tests: Res<Vec<_>> = values.map(|v| Expr::test(&flag, v)).collect();
+            exprs.push(Expr::any(tests?));
+        }
+    }
+    for name in ["size", "mtime", "atime", "ctime", "newer"] {
+        if let Some(value) = matches.value_of(name) {
+            exprs.push(Expr::test(&format!("--{}", name), value)?);
+        }
+    }
+    if matches.is_present("empty") {
+        exprs.push(Expr::Empty);
+    }
+    exprs.extend(expr::parse(expr)?);
+
+    // without an action of its own, every match is printed, unless the
+    // matches are only gathered to look for duplicates
+    let mut expr = Expr::all(exprs);
+    let duplicates = matches
+        .is_present("duplicates")
+        .then(|| {
+            parse_size(
+                matches.value_of("dup_min_size").unwrap_or("1"),
+                "--dup-min-size",
+            )
         })
-        .collect();
+        .transpose()?;
+    if !expr.has_action() && duplicates.is_none() {
+        expr = Expr::And(Box::new(expr), Box::new(Expr::Print));
+    }
+    if expr.contains(&|e| matches!(e, Expr::Delete))
+        && !expr.has_test()
+        && !matches.is_present("force")
+    {
+        return Err("Refusing to -delete every entry without a test, use --force".into());
+    }
 
+    // a directory can only be deleted once everything in it has been,
+    // by which time it is too late to prune it, so find refuses this too
+    let contents_first = expr.contains(&|e| matches!(e, Expr::Delete));
+    if contents_first && expr.contains(&|e| matches!(e, Expr::Prune)) {
+        return Err("-prune has no effect with -delete".into());
+    }
+    let threads = match parse_count(matches.value_of("threads"), "--threads")? {
+        Some(0) => return Err("Invalid --threads \"0\"".into()),
+        threads => threads.unwrap_or(1),
+    };
     Ok(Config {
         paths,
-        names: names?,
-        entry_types,
+        expr,
+        dry_run: matches.is_present("dry_run"),
+        json: matches.is_present("json"),
+        duplicates,
+        quiet_errors: matches.is_present("quiet_errors"),
+        watch: matches.is_present("watch"),
+        walk: WalkOptions {
+            min_depth: parse_count(matches.value_of("min_depth"), "--min-depth")?,
+            max_depth: parse_count(matches.value_of("max_depth"), "--max-depth")?,
+            follow: if matches.is_present("follow") {
+                Follow::Always
+            } else if matches.is_present("follow_roots") {
+                Follow::Roots
+            } else {
+                Follow::Never
+            },
+            same_file_system: matches.is_present("xdev"),
+            exclude_dirs: parse_globs(matches.values_of("exclude_dir"), "--exclude-dir")?,
+            respect_ignore: matches.is_present("respect_ignore"),
+            contents_first,
+            threads,
+            sort: matches.value_of("sort").map(|key| match key {
+                "size" => SortKey::Size,
+                "mtime" => SortKey::Mtime,
+                _ => SortKey::Name,
+            }),
+        },
     })
 }
 
-pub fn run(config: Config) -> Res<()> {
-    for path in config.paths {
-        for entry in WalkDir::new(path) {
-            match entry {
-                Err(e) => eprintln!("{}", e),
-                Ok(entry) => {
-                    let filetype = entry.file_type();
-                    let filename = entry.file_name().to_string_lossy();
-                    if filetype.is_dir() && config.entry_types.contains(&Dir) {
-                        if is_match(&config.names, &filename) {
-                            println!("{}", entry.path().display())
-                        }
-                    }
+fn parse_globs<'a>(values: Option<impl Iterator<Item = &'a str>>, flag: &str) -> Res<GlobSet> {
+    let mut builder = GlobSetBuilder::new();
+    for value in values.into_iter().flatten() {
+        let glob = Glob::new(value).map_err(|_| format!("Invalid {} \"{}\"", flag, value))?;
+        builder.add(glob);
+    }
+    Ok(builder.build()?)
+}
+
+fn parse_size(value: &str, flag: &str) -> Res<u64> {
+    let (number, unit) = expr::split_unit(value);
+    number
+        .parse::<u64>()
+        .ok()
+        .and_then(|n| n.checked_mul(unit))
+        .ok_or_else(|| format!("Invalid {} \"{}\"", flag, value).into())
+}
+
+fn parse_count(value: Option<&str>, flag: &str) -> Res<Option<usize>> {
+    value
+        .map(|v| {
+            v.parse()
+                .map_err(|_| format!("Invalid {} \"{}\"", flag, v).into())
+        })
+        .transpose()
+}
 
-                    if filetype.is_symlink() && config.entry_types.contains(&Link) {
-                        if is_match(&config.names, &filename) {
-                            println!("{}", entry.path().display())
-                        }
-                    }
+// whether every entry could be read and acted on, as findr exits non-zero
+// when one couldn't
+pub fn run(config: Config) -> Res<bool> {
+    let Config {
+        paths,
+        expr,
+        dry_run,
+        json,
+        duplicates,
+        quiet_errors,
+        watch,
+        walk,
+    } = config;
+    let errors = Errors::new(quiet_errors);
+    let now = SystemTime::now();
+    let files = Mutex::new(vec![]);
+    // set up before the walk, which adds the directories it goes into
+    let watcher = if watch {
+        Some(Watcher::new(&paths, &walk, &errors)?)
+    } else {
+        None
+    };
+    let visit = |entry: &Entry| {
+        let env = Env {
+            now,
+            dry_run,
+            json,
+            prune: Cell::new(false),
+            errors: &errors,
+        };
+        let matched = expr.eval(entry, &env);
+        if matched && duplicates.is_some() && entry.file_type().is_file() {
+            if let Ok(metadata) = entry.metadata() {
+                files
+                    .lock()
+                    .unwrap()
+                    .push((entry.path().to_path_buf(), metadata));
+            }
+        }
+        let pruned = env.prune.get();
+        let descends = walk.max_depth.is_none_or(|max| entry.depth() < max);
+        if let Some(watcher) = &watcher {
+            if !pruned && entry.file_type().is_dir() && descends {
+                watcher.add(entry.path(), entry.depth());
+            }
+        }
+        pruned
+    };
+    for path in paths.iter().take_while(|_| !errors.stopped()) {
+        walk::walk(path, &walk, &errors, &visit);
+    }
+    expr.finish(&errors);
 
-                    if filetype.is_file() && config.entry_types.contains(&File) {
-                        if is_match(&config.names, &filename) {
-                            println!("{}", entry.path().display())
-                        }
-                    }
+    // groups are separated by a blank line, or are each an array of JSON
+    if let Some(min_size) = duplicates {
+        let groups = dupes::find(mem::take(&mut files.lock().unwrap()), min_size, &errors);
+        for (i, group) in groups.iter().enumerate() {
+            let mut out = vec![];
+            if json {
+                let paths: Vec<_> = group.iter().map(|p| p.to_string_lossy()).collect();
+                out = format!("{}\n", serde_json::json!(paths)).into_bytes();
+            } else {
+                if i > 0 {
+                    out.push(b'\n');
+                }
+                for path in group {
+                    out.extend(expr::line(path, b'\n'));
                 }
             }
+            if !print(&out, &errors) {
+                break;
+            }
         }
     }
-    Ok(())
+
+    if let Some(watcher) = &watcher {
+        watcher.run(&visit, &|| expr.finish(&errors))?;
+    }
+    Ok(!errors.failed())
 }
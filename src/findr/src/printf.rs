@@ -0,0 +1,297 @@
+use crate::entry::Entry;
+use crate::Res;
+use serde_json::{json, Value};
+use std::fs::{self, FileType, Metadata};
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::{FileTypeExt, MetadataExt};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// ls -l shows the year instead of the time for files older than this
+const SIX_MONTHS: i64 = 31_556_952 / 2;
+
+#[derive(Debug, Clone, Copy)]
+enum Directive {
+    // %p
+    Path,
+    // %f
+    Name,
+    // %h
+    Dir,
+    // %s
+    Size,
+    // %m
+    Mode,
+    // %M
+    Perms,
+    // %u
+    User,
+    // %T@
+    Mtime,
+    // %y
+    Type,
+    // %d
+    Depth,
+}
+
+#[derive(Debug)]
+enum Piece {
+    Text(Vec<u8>),
+    Directive(Directive),
+}
+
+// a parsed -printf format
+#[derive(Debug)]
+pub struct Format(Vec<Piece>);
+
+impl Format {
+    pub fn parse(format: &str) -> Res<Format> {
+        let mut pieces = vec![];
+        let mut text = vec![];
+        let mut chars = format.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => {
+                    let escaped = match chars.next() {
+                        Some('n') => '\n',
+                        Some('t') => '\t',
+                        Some('r') => '\r',
+                        Some('0') => '\0',
+                        Some('\\') => '\\',
+                        Some(other) => {
+                            return Err(format!("Invalid -printf escape \"\\{}\"", other).into())
+                        }
+                        None => return Err("Invalid -printf format ending in \"\\\"".into()),
+                    };
+                    text.push(escaped as u8);
+                }
+                '%' => {
+                    let directive = match chars.next() {
+                        Some('%') => {
+                            text.push(b'%');
+                            continue;
+                        }
+                        Some('p') => Directive::Path,
+                        Some('f') => Directive::Name,
+                        Some('h') => Directive::Dir,
+                        Some('s') => Directive::Size,
+                        Some('m') => Directive::Mode,
+                        Some('M') => Directive::Perms,
+                        Some('u') => Directive::User,
+                        Some('y') => Directive::Type,
+                        Some('d') => Directive::Depth,
+                        Some('T') if chars.next() == Some('@') => Directive::Mtime,
+                        Some('T') => return Err("Only %T@ is supported for -printf".into()),
+                        Some(other) => {
+                            return Err(format!("Invalid -printf directive \"%{}\"", other).into())
+                        }
+                        None => return Err("Invalid -printf format ending in \"%\"".into()),
+                    };
+                    if !text.is_empty() {
+                        pieces.push(Piece::Text(std::mem::take(&mut text)));
+                    }
+                    pieces.push(Piece::Directive(directive));
+                }
+                _ => {
+                    let mut buf = [0; 4];
+                    text.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+                }
+            }
+        }
+        if !text.is_empty() {
+            pieces.push(Piece::Text(text));
+        }
+        Ok(Format(pieces))
+    }
+
+    // paths are written as their raw bytes rather than lossily
+    pub fn render(&self, entry: &Entry) -> Vec<u8> {
+        let metadata = entry.metadata().ok();
+        let mut out = vec![];
+        for piece in &self.0 {
+            match piece {
+                Piece::Text(text) => out.extend_from_slice(text),
+                Piece::Directive(directive) => {
+                    render_directive(*directive, entry, metadata.as_ref(), &mut out)
+                }
+            }
+        }
+        out
+    }
+}
+
+fn render_directive(
+    directive: Directive,
+    entry: &Entry,
+    metadata: Option<&Metadata>,
+    out: &mut Vec<u8>,
+) {
+    let path = entry.path();
+    let text = match directive {
+        Directive::Path => return out.extend_from_slice(path.as_os_str().as_bytes()),
+        Directive::Name => return out.extend_from_slice(entry.file_name().as_bytes()),
+        Directive::Dir => {
+            // find prints "." for a path with no directory part
+            let dir = match path.parent() {
+                Some(dir) if dir != Path::new("") => dir,
+                _ => Path::new("."),
+            };
+            return out.extend_from_slice(dir.as_os_str().as_bytes());
+        }
+        Directive::Type => type_char(entry.file_type()).to_string(),
+        Directive::Depth => entry.depth().to_string(),
+        _ => match metadata {
+            None => "?".to_string(),
+            Some(metadata) => match directive {
+                Directive::Size => metadata.len().to_string(),
+                Directive::Mode => format!("{:o}", metadata.mode() & 0o7777),
+                Directive::Perms => symbolic_mode(entry.file_type(), metadata.mode()),
+                Directive::User => user_name(metadata.uid()),
+                _ => format!("{}.{:09}0", metadata.mtime(), metadata.mtime_nsec()),
+            },
+        },
+    };
+    out.extend_from_slice(text.as_bytes());
+}
+
+// the single letter find and -type use for each kind of entry
+pub fn type_char(filetype: FileType) -> char {
+    if filetype.is_dir() {
+        'd'
+    } else if filetype.is_file() {
+        'f'
+    } else if filetype.is_symlink() {
+        'l'
+    } else if filetype.is_block_device() {
+        'b'
+    } else if filetype.is_char_device() {
+        'c'
+    } else if filetype.is_fifo() {
+        'p'
+    } else if filetype.is_socket() {
+        's'
+    } else {
+        '?'
+    }
+}
+
+// permissions as ls -l shows them, e.g. "drwxr-xr-x"
+pub fn symbolic_mode(filetype: FileType, mode: u32) -> String {
+    let kind = match type_char(filetype) {
+        'f' => '-',
+        c => c,
+    };
+    let mut out = String::from(kind);
+    // each class is read, write and execute, with the execute slot also
+    // showing setuid, setgid or sticky
+    for (shift, special, set) in [(6, 0o4000, 's'), (3, 0o2000, 's'), (0, 0o1000, 't')] {
+        let bits = (mode >> shift) & 0o7;
+        out.push(if bits & 0o4 != 0 { 'r' } else { '-' });
+        out.push(if bits & 0o2 != 0 { 'w' } else { '-' });
+        out.push(match (bits & 0o1 != 0, mode & special != 0) {
+            (true, true) => set,
+            (false, true) => set.to_ascii_uppercase(),
+            (true, false) => 'x',
+            (false, false) => '-',
+        });
+    }
+    out
+}
+
+// falls back to the numeric id for users without a name
+pub fn user_name(uid: u32) -> String {
+    uzers::get_user_by_uid(uid)
+        .map(|user| user.name().to_string_lossy().into_owned())
+        .unwrap_or_else(|| uid.to_string())
+}
+
+// falls back to the numeric id for groups without a name
+fn group_name(gid: u32) -> String {
+    uzers::get_group_by_gid(gid)
+        .map(|group| group.name().to_string_lossy().into_owned())
+        .unwrap_or_else(|| gid.to_string())
+}
+
+// the names fd uses for each kind of entry
+fn type_name(filetype: FileType) -> &'static str {
+    match type_char(filetype) {
+        'd' => "directory",
+        'f' => "file",
+        'l' => "symlink",
+        'b' => "block",
+        'c' => "char",
+        'p' => "fifo",
+        's' => "socket",
+        _ => "unknown",
+    }
+}
+
+// one --json object, with nulls for whatever couldn't be read
+pub fn json(entry: &Entry) -> Value {
+    let metadata = entry.metadata().ok();
+    let metadata = metadata.as_ref();
+    json!({
+        "path": entry.path().to_string_lossy(),
+        "type": type_name(entry.file_type()),
+        "size": metadata.map(|m| m.len()),
+        "mode": metadata.map(|m| format!("{:o}", m.mode() & 0o7777)),
+        "mtime": metadata.map(|m| m.mtime()),
+        "depth": entry.depth(),
+    })
+}
+
+// a line of -ls, laid out as find does: inode, 1K blocks, permissions,
+// links, owner, group, size, modification time and path
+pub fn long_listing(entry: &Entry, metadata: &Metadata, now: SystemTime) -> Vec<u8> {
+    let size = if entry.file_type().is_block_device() || entry.file_type().is_char_device() {
+        let rdev = metadata.rdev();
+        format!("{:>3}, {:>3}", libc::major(rdev), libc::minor(rdev))
+    } else {
+        metadata.len().to_string()
+    };
+    let line = format!(
+        "{:>9} {:>6} {} {:>3} {:<8} {:<8} {:>8} {} ",
+        metadata.ino(),
+        metadata.blocks().div_ceil(2),
+        symbolic_mode(entry.file_type(), metadata.mode()),
+        metadata.nlink(),
+        user_name(metadata.uid()),
+        group_name(metadata.gid()),
+        size,
+        list_time(metadata.mtime(), now),
+    );
+    let mut out = line.into_bytes();
+    out.extend_from_slice(entry.path().as_os_str().as_bytes());
+    if entry.file_type().is_symlink() {
+        if let Ok(target) = fs::read_link(entry.path()) {
+            out.extend_from_slice(b" -> ");
+            out.extend_from_slice(target.as_os_str().as_bytes());
+        }
+    }
+    out.push(b'\n');
+    out
+}
+
+// "Mar 29 14:05" in local time, or "Mar 29  2022" for times more than six
+// months ago or in the future
+fn list_time(mtime: i64, now: SystemTime) -> String {
+    const MONTHS: [&str; 12] = [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ];
+    let now = now
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() as i64);
+    // SAFETY: localtime_r only writes to the tm it is given
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    let time: libc::time_t = mtime;
+    unsafe { libc::localtime_r(&time, &mut tm) };
+    let month = MONTHS[tm.tm_mon.clamp(0, 11) as usize];
+    if mtime <= now && now - mtime < SIX_MONTHS {
+        format!(
+            "{} {:>2} {:02}:{:02}",
+            month, tm.tm_mday, tm.tm_hour, tm.tm_min
+        )
+    } else {
+        format!("{} {:>2}  {}", month, tm.tm_mday, tm.tm_year + 1900)
+    }
+}
@@ -0,0 +1,422 @@
+use crate::entry::Entry;
+use crate::errors::Errors;
+use globset::GlobSet;
+use ignore::{WalkBuilder, WalkState};
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::ffi::OsStr;
+use std::fs;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use walkdir::WalkDir;
+
+// which symlinks are followed: none (the default, find -P), only the
+// starting paths (-H) or all of them (-L)
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Follow {
+    Never,
+    Roots,
+    Always,
+}
+
+// what --sort orders the entries by
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum SortKey {
+    Name,
+    Size,
+    Mtime,
+}
+
+// how each starting path is walked
+#[derive(Debug)]
+pub struct WalkOptions {
+    pub min_depth: Option<usize>,
+    pub max_depth: Option<usize>,
+    pub follow: Follow,
+    pub same_file_system: bool,
+    pub exclude_dirs: GlobSet,
+    // skip what .gitignore, .ignore, the global git excludes and the
+    // hidden-file rules leave out
+    pub respect_ignore: bool,
+    // visit a directory's contents before the directory, for -delete
+    pub contents_first: bool,
+    // more than one reads directories in parallel
+    pub threads: usize,
+    // visit entries in order rather than as they are found
+    pub sort: Option<SortKey>,
+}
+
+// handed each entry, returning true to prune a directory from the rest of
+// the walk, and called from every thread of a parallel walk
+pub type Visit<'a> = dyn Fn(&Entry) -> bool + Sync + 'a;
+
+// excluded directories are never read, unlike pruned ones, but the
+// starting paths are always searched
+pub fn is_excluded(exclude_dirs: &GlobSet, depth: usize, is_dir: bool, name: &OsStr) -> bool {
+    depth > 0 && is_dir && exclude_dirs.is_match(name)
+}
+
+// walks one starting path, handing each entry to visit
+pub fn walk(path: &str, opts: &WalkOptions, errors: &Errors, visit: &Visit) {
+    // a parallel walk can't tell when it has left a directory, so it is
+    // sorted into contents first order instead
+    if opts.sort.is_some() || (opts.contents_first && opts.threads > 1) {
+        walk_sorted(path, opts, errors, visit)
+    } else {
+        walk_unsorted(path, opts, errors, visit)
+    }
+}
+
+fn walk_unsorted(path: &str, opts: &WalkOptions, errors: &Errors, visit: &Visit) {
+    if opts.threads > 1 {
+        walk_parallel(path, opts, errors, visit)
+    } else if opts.respect_ignore {
+        walk_ignoring(path, opts, errors, visit)
+    } else {
+        walk_all(path, opts, errors, visit)
+    }
+}
+
+// collects the whole walk before visiting any of it, so pruning saves
+// nothing but still leaves out what is below a pruned directory
+fn walk_sorted(path: &str, opts: &WalkOptions, errors: &Errors, visit: &Visit) {
+    let entries = Mutex::new(vec![]);
+    walk_unsorted(path, opts, errors, &|entry| {
+        entries.lock().unwrap().push(entry.clone());
+        false
+    });
+    let mut entries = entries.into_inner().unwrap();
+    entries.sort_by(|a, b| compare_paths(a.path(), b.path(), opts.contents_first));
+    // sizes and mtimes order the whole walk, with ties left in tree order
+    if let Some(key @ (SortKey::Size | SortKey::Mtime)) = opts.sort {
+        entries.sort_by_cached_key(|entry| sort_value(entry, key))
+    }
+    if opts.contents_first {
+        visit_contents_first(entries, errors, visit)
+    } else {
+        visit_dirs_first(entries, errors, visit)
+    }
+}
+
+// entries that can't be read sort as though empty and from 1970
+fn sort_value(entry: &Entry, key: SortKey) -> i128 {
+    match (entry.metadata(), key) {
+        (Ok(metadata), SortKey::Size) => metadata.len().into(),
+        (Ok(metadata), SortKey::Mtime) => {
+            i128::from(metadata.mtime()) * 1_000_000_000 + i128::from(metadata.mtime_nsec())
+        }
+        _ => 0,
+    }
+}
+
+// orders paths a component at a time, so that a directory comes just
+// before its contents, or just after them with contents_first, and
+// siblings are ordered by name
+fn compare_paths(a: &Path, b: &Path, contents_first: bool) -> Ordering {
+    let (mut x, mut y) = (a.components(), b.components());
+    loop {
+        match (x.next(), y.next()) {
+            (Some(p), Some(q)) if p == q => continue,
+            (Some(p), Some(q)) => return p.cmp(&q),
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) if contents_first => return Ordering::Greater,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) if contents_first => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+        }
+    }
+}
+
+// visits sorted entries, except that a directory sorted behind any of its
+// contents is visited just ahead of them instead, so that pruning it still
+// leaves them out
+fn visit_dirs_first(entries: Vec<Entry>, errors: &Errors, visit: &Visit) {
+    let mut dirs: HashMap<PathBuf, Entry> = entries
+        .iter()
+        .filter(|entry| entry.file_type().is_dir())
+        .map(|entry| (entry.path().to_path_buf(), entry.clone()))
+        .collect();
+    let mut pruned = HashSet::new();
+    for entry in entries {
+        if entry.file_type().is_dir() && dirs.remove(entry.path()).is_none() {
+            continue;
+        }
+        visit_dir_first(&entry, &mut dirs, &mut pruned, errors, visit);
+    }
+}
+
+fn visit_dir_first(
+    entry: &Entry,
+    dirs: &mut HashMap<PathBuf, Entry>,
+    pruned: &mut HashSet<PathBuf>,
+    errors: &Errors,
+    visit: &Visit,
+) {
+    if let Some(dir) = parent(entry).and_then(|dir| dirs.remove(dir)) {
+        visit_dir_first(&dir, dirs, pruned, errors, visit);
+    }
+    if errors.stopped() {
+        return;
+    }
+    if (parent(entry).is_some_and(|dir| pruned.contains(dir)) || visit(entry))
+        && entry.file_type().is_dir()
+    {
+        pruned.insert(entry.path().to_path_buf());
+    }
+}
+
+// visits sorted entries, except that a directory sorted ahead of any of
+// its contents waits until they have all been visited
+fn visit_contents_first(entries: Vec<Entry>, errors: &Errors, visit: &Visit) {
+    let mut left: HashMap<PathBuf, usize> = HashMap::new();
+    for dir in entries.iter().filter_map(parent) {
+        *left.entry(dir.to_path_buf()).or_default() += 1;
+    }
+    let mut held = HashMap::new();
+    for entry in entries {
+        if left.get(entry.path()).is_some_and(|n| *n > 0) {
+            held.insert(entry.path().to_path_buf(), entry);
+            continue;
+        }
+        let mut next = Some(entry);
+        while let Some(entry) = next.take() {
+            if errors.stopped() {
+                return;
+            }
+            visit(&entry);
+            if let Some(dir) = parent(&entry) {
+                let n = left.get_mut(dir).unwrap();
+                *n -= 1;
+                if *n == 0 {
+                    next = held.remove(dir);
+                }
+            }
+        }
+    }
+}
+
+// the directory an entry was found in, unless it is a starting path
+fn parent(entry: &Entry) -> Option<&Path> {
+    match entry.depth() {
+        0 => None,
+        _ => entry.path().parent(),
+    }
+}
+
+// holds each directory back until the walk has left it, so that its
+// contents are visited first. the walk itself still reads a directory
+// before its contents, which is when excluded ones are left out
+#[derive(Default)]
+struct Held(Vec<Entry>);
+
+impl Held {
+    // visits entry, or holds it back if it is a directory, once the
+    // directories it is not inside of have been visited
+    fn visit(&mut self, entry: Entry, errors: &Errors, visit: &Visit) {
+        while self
+            .0
+            .last()
+            .is_some_and(|dir| !entry.path().starts_with(dir.path()))
+        {
+            self.leave(errors, visit);
+        }
+        if entry.file_type().is_dir() {
+            self.0.push(entry);
+        } else if !errors.stopped() {
+            visit(&entry);
+        }
+    }
+
+    fn leave(&mut self, errors: &Errors, visit: &Visit) {
+        if let Some(dir) = self.0.pop() {
+            if !errors.stopped() {
+                visit(&dir);
+            }
+        }
+    }
+
+    fn finish(&mut self, errors: &Errors, visit: &Visit) {
+        while !self.0.is_empty() {
+            self.leave(errors, visit);
+        }
+    }
+}
+
+fn walk_all(path: &str, opts: &WalkOptions, errors: &Errors, visit: &Visit) {
+    // depths count from each starting path, which is always at depth 0
+    let mut walker = WalkDir::new(path)
+        .follow_links(opts.follow == Follow::Always)
+        .follow_root_links(opts.follow != Follow::Never)
+        .same_file_system(opts.same_file_system);
+    if let Some(depth) = opts.min_depth {
+        walker = walker.min_depth(depth);
+    }
+    if let Some(depth) = opts.max_depth {
+        walker = walker.max_depth(depth);
+    }
+
+    let mut held = Held::default();
+    let mut walker = walker.into_iter().filter_entry(|e| {
+        !is_excluded(
+            &opts.exclude_dirs,
+            e.depth(),
+            e.file_type().is_dir(),
+            e.file_name(),
+        )
+    });
+    while let Some(entry) = walker.next() {
+        let entry = match entry {
+            Ok(entry) => Entry::from_walkdir(entry, opts.follow != Follow::Never),
+            Err(e) => {
+                let broken = match (e.path(), e.io_error()) {
+                    (Some(path), Some(err)) => Entry::broken_link(path, e.depth(), err),
+                    _ => None,
+                };
+                match broken {
+                    // walkdir reports these whatever the minimum depth
+                    Some(entry) if entry.depth() >= opts.min_depth.unwrap_or(0) => entry,
+                    Some(_) => continue,
+                    // this includes each symlink that loops back to a parent
+                    None => {
+                        match (e.path(), e.io_error()) {
+                            (Some(path), Some(err)) => errors.io(path, err),
+                            (Some(path), None) => errors.report(path, "File system loop detected"),
+                            (None, _) => errors.report(Path::new(path), e),
+                        }
+                        continue;
+                    }
+                }
+            }
+        };
+        if opts.contents_first {
+            held.visit(entry, errors, visit);
+        } else if visit(&entry) && entry.file_type().is_dir() {
+            walker.skip_current_dir();
+        }
+        if errors.stopped() {
+            return;
+        }
+    }
+    held.finish(errors, visit);
+}
+
+// the ignore crate's walkers, which only leave out ignored and hidden
+// files when asked to
+fn ignore_walker(path: &str, opts: &WalkOptions) -> WalkBuilder {
+    let exclude_dirs = opts.exclude_dirs.clone();
+    let mut builder = WalkBuilder::new(path);
+    builder
+        .standard_filters(opts.respect_ignore)
+        .min_depth(opts.min_depth)
+        .max_depth(opts.max_depth)
+        .follow_links(opts.follow == Follow::Always)
+        .same_file_system(opts.same_file_system)
+        .threads(opts.threads)
+        .filter_entry(move |e| {
+            let is_dir = e.file_type().is_some_and(|t| t.is_dir());
+            !is_excluded(&exclude_dirs, e.depth(), is_dir, e.file_name())
+        });
+    builder
+}
+
+// the ignore crate's walkers always look through a starting path that is
+// a symlink, so one that isn't to be followed is left to walkdir, which
+// reports the link itself and goes no further
+fn is_unfollowed_link(path: &str, opts: &WalkOptions) -> bool {
+    opts.follow == Follow::Never
+        && fs::symlink_metadata(path).is_ok_and(|m| m.file_type().is_symlink())
+}
+
+fn walk_ignoring(path: &str, opts: &WalkOptions, errors: &Errors, visit: &Visit) {
+    if is_unfollowed_link(path, opts) {
+        return walk_all(path, opts, errors, visit);
+    }
+    // this walker can't skip a directory once it has been reached, so the
+    // contents of pruned directories are passed over instead
+    let mut pruned: Vec<PathBuf> = vec![];
+    let mut held = Held::default();
+    for entry in ignore_walker(path, opts).build() {
+        let Some(entry) = from_ignore(entry, path, opts, errors) else {
+            continue;
+        };
+        while pruned
+            .last()
+            .is_some_and(|dir| !entry.path().starts_with(dir))
+        {
+            pruned.pop();
+        }
+        if opts.contents_first {
+            held.visit(entry, errors, visit);
+        } else if pruned.is_empty() && visit(&entry) && entry.file_type().is_dir() {
+            pruned.push(entry.path().to_path_buf());
+        }
+        if errors.stopped() {
+            return;
+        }
+    }
+    held.finish(errors, visit);
+}
+
+fn walk_parallel(path: &str, opts: &WalkOptions, errors: &Errors, visit: &Visit) {
+    if is_unfollowed_link(path, opts) {
+        return walk_all(path, opts, errors, visit);
+    }
+    ignore_walker(path, opts).build_parallel().run(|| {
+        Box::new(|entry| match from_ignore(entry, path, opts, errors) {
+            _ if errors.stopped() => WalkState::Quit,
+            Some(entry) if visit(&entry) && entry.file_type().is_dir() => WalkState::Skip,
+            _ => WalkState::Continue,
+        })
+    })
+}
+
+fn from_ignore(
+    entry: Result<ignore::DirEntry, ignore::Error>,
+    root: &str,
+    opts: &WalkOptions,
+    errors: &Errors,
+) -> Option<Entry> {
+    let e = match entry {
+        Ok(entry) => return Entry::from_ignore(entry, opts.follow != Follow::Never),
+        Err(e) => e,
+    };
+    let broken = match (error_path(&e), e.io_error()) {
+        (Some(path), Some(err)) => Entry::broken_link(path, e.depth().unwrap_or(0), err),
+        _ => None,
+    };
+    match broken {
+        Some(entry) if entry.depth() >= opts.min_depth.unwrap_or(0) => Some(entry),
+        Some(_) => None,
+        None => {
+            let path = error_path(&e).unwrap_or(Path::new(root));
+            match e.io_error() {
+                Some(err) => errors.io(path, err),
+                None => errors.report(path, error_reason(&e)),
+            }
+            None
+        }
+    }
+}
+
+fn error_path(err: &ignore::Error) -> Option<&Path> {
+    match err {
+        ignore::Error::WithPath { path, .. } => Some(path),
+        ignore::Error::Loop { child, .. } => Some(child),
+        ignore::Error::WithDepth { err, .. } | ignore::Error::WithLineNumber { err, .. } => {
+            error_path(err)
+        }
+        _ => None,
+    }
+}
+
+// what went wrong, without the path that is reported ahead of it
+fn error_reason(err: &ignore::Error) -> String {
+    match err {
+        ignore::Error::WithPath { err, .. } | ignore::Error::WithDepth { err, .. } => {
+            error_reason(err)
+        }
+        ignore::Error::Loop { .. } => "File system loop detected".to_string(),
+        _ => err.to_string(),
+    }
+}
@@ -0,0 +1,194 @@
+use crate::entry::Entry;
+use crate::errors::{flush, Errors};
+use crate::walk::{self, Follow, Visit, WalkOptions};
+use inotify::{EventMask, Inotify, WatchDescriptor, WatchMask};
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+// the changes that can make an entry newly match: being created, written
+// or renamed into place. moves away and deletions only stop the watching
+const MASK: WatchMask = WatchMask::CREATE
+    .union(WatchMask::CLOSE_WRITE)
+    .union(WatchMask::MOVED_TO)
+    .union(WatchMask::MOVED_FROM)
+    .union(WatchMask::DELETE_SELF);
+
+// inotify watches single directories, so every directory the walk goes
+// into is watched as it is visited, each with its depth below its starting
+// path. that leaves out those that are pruned or excluded, and nothing
+// that changes between the walk and the watching is missed
+pub struct Watcher<'a> {
+    inotify: Mutex<Inotify>,
+    dirs: Mutex<HashMap<WatchDescriptor, (PathBuf, usize)>>,
+    opts: &'a WalkOptions,
+    errors: &'a Errors,
+}
+
+impl<'a> Watcher<'a> {
+    pub fn new(paths: &[String], opts: &'a WalkOptions, errors: &'a Errors) -> io::Result<Self> {
+        let watcher = Watcher {
+            inotify: Mutex::new(Inotify::init()?),
+            dirs: Mutex::new(HashMap::new()),
+            opts,
+            errors,
+        };
+        // the walk never visits what is above the minimum depth, which is
+        // never tested and so never pruned either
+        for path in paths {
+            watcher.add_shallow(Path::new(path), 0);
+        }
+        Ok(watcher)
+    }
+
+    pub fn add(&self, dir: &Path, depth: usize) {
+        let added = self.inotify.lock().unwrap().watches().add(dir, MASK);
+        match added {
+            Ok(wd) => {
+                let mut dirs = self.dirs.lock().unwrap();
+                dirs.insert(wd, (dir.to_path_buf(), depth));
+            }
+            Err(e) => self.errors.io(dir, &e),
+        }
+    }
+
+    // once the walk is over, hands each entry that is created, written or
+    // renamed to visit, calling finish after each round of changes
+    pub fn run(&self, visit: &Visit, finish: &dyn Fn()) -> io::Result<()> {
+        let mut buf = vec![0; 64 * 1024];
+        loop {
+            // what was printed needn't end in a newline, as with -print0,
+            // so it is flushed before waiting on more changes
+            flush(self.errors);
+            if self.errors.stopped() {
+                return Ok(());
+            }
+            let events: Vec<(WatchDescriptor, EventMask, Option<OsString>)> = self
+                .inotify
+                .lock()
+                .unwrap()
+                .read_events_blocking(&mut buf)?
+                .map(|event| (event.wd, event.mask, event.name.map(OsString::from)))
+                .collect();
+            for (wd, mask, name) in events {
+                self.changed(wd, mask, name, visit);
+            }
+            finish();
+        }
+    }
+
+    fn add_shallow(&self, dir: &Path, depth: usize) {
+        if depth >= self.opts.min_depth.unwrap_or(0) || !self.descends(dir, depth) {
+            return;
+        }
+        self.add(dir, depth);
+        for child in self.read_dir(dir) {
+            self.add_shallow(&child, depth + 1);
+        }
+    }
+
+    // whether a directory's contents are within reach, leaving symlinks
+    // to directories alone so that they can't loop
+    fn descends(&self, dir: &Path, depth: usize) -> bool {
+        let is_dir = fs::symlink_metadata(dir).is_ok_and(|m| m.is_dir());
+        is_dir
+            && self.opts.max_depth.is_none_or(|max| depth < max)
+            && !walk::is_excluded(
+                &self.opts.exclude_dirs,
+                depth,
+                is_dir,
+                dir.file_name().unwrap_or_default(),
+            )
+    }
+
+    fn read_dir(&self, dir: &Path) -> Vec<PathBuf> {
+        match fs::read_dir(dir) {
+            Ok(entries) => entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.path())
+                .collect(),
+            Err(e) => {
+                self.errors.io(dir, &e);
+                vec![]
+            }
+        }
+    }
+
+    fn changed(&self, wd: WatchDescriptor, mask: EventMask, name: Option<OsString>, visit: &Visit) {
+        if mask.contains(EventMask::Q_OVERFLOW) {
+            let reason = "Too many changes at once, some were missed";
+            self.errors.report(Path::new("--watch"), reason);
+            return;
+        }
+        let Some((dir, depth)) = self.dirs.lock().unwrap().get(&wd).cloned() else {
+            return;
+        };
+        if mask.contains(EventMask::IGNORED) {
+            self.dirs.lock().unwrap().remove(&wd);
+            return;
+        }
+        let Some(name) = name else {
+            return;
+        };
+        let path = dir.join(name);
+        if mask.contains(EventMask::MOVED_FROM) {
+            // a directory moved away keeps its watches under a stale path
+            self.forget(&path);
+        } else if mask.contains(EventMask::CREATE) && !mask.contains(EventMask::ISDIR) {
+            // new files are only looked at once they have been written
+            if fs::symlink_metadata(&path).is_ok_and(|m| !m.is_file()) {
+                self.found(path, depth + 1, visit);
+            }
+        } else {
+            self.found(path, depth + 1, visit);
+        }
+    }
+
+    fn forget(&self, dir: &Path) {
+        let mut dirs = self.dirs.lock().unwrap();
+        let gone: Vec<_> = dirs
+            .iter()
+            .filter(|(_, (path, _))| path.starts_with(dir))
+            .map(|(wd, _)| wd.clone())
+            .collect();
+        for wd in gone {
+            dirs.remove(&wd);
+            let _ = self.inotify.lock().unwrap().watches().remove(wd);
+        }
+    }
+
+    // visits a new entry as the walk would have, which watches a new
+    // directory, then visits its contents, which may have arrived with it
+    fn found(&self, path: PathBuf, depth: usize, visit: &Visit) {
+        if self.opts.max_depth.is_some_and(|max| depth > max) {
+            return;
+        }
+        let follow = self.opts.follow == Follow::Always;
+        let entry = match Entry::from_path(path.clone(), depth, follow) {
+            Ok(entry) => entry,
+            // it was already gone again
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return,
+            Err(e) => return self.errors.io(&path, &e),
+        };
+        let is_dir = entry.file_type().is_dir();
+        let name = entry.file_name();
+        if walk::is_excluded(&self.opts.exclude_dirs, depth, is_dir, name) {
+            return;
+        }
+        // visit watches the directories it is given, but those above the
+        // minimum depth are never tested, so they are watched here instead
+        let shallow = depth < self.opts.min_depth.unwrap_or(0);
+        let pruned = !shallow && visit(&entry);
+        if !pruned && self.descends(entry.path(), depth) {
+            if shallow {
+                self.add(entry.path(), depth);
+            }
+            for child in self.read_dir(entry.path()) {
+                self.found(child, depth + 1, visit);
+            }
+        }
+    }
+}
@@ -5,6 +5,10 @@ use std::{borrow::Cow, fs, path::Path};
 
 type TestResult = Result<(), Box<dyn std::error::Error>>;
 
+// a tree built afresh for tests that change it or need particular times,
+// links or permissions, which is removed once dropped
+type Fixture = Result<tempfile::TempDir, Box<dyn std::error::Error>>;
+
 const PRG: &str = "findr";
 
 // --------------------------------------------------
@@ -26,12 +30,12 @@ fn gen_bad_file() -> String {
 #[test]
 fn skips_bad_dir() -> TestResult {
     let bad = gen_bad_file();
-    let expected = format!("{}: .* [(]os error [23][)]", &bad);
+    let expected = format!("findr: {}: No such file or directory\n", &bad);
     Command::cargo_bin(PRG)?
         .arg(&bad)
         .assert()
-        .success()
-        .stderr(predicate::str::is_match(expected)?);
+        .failure()
+        .stderr(expected);
     Ok(())
 }
 
@@ -39,7 +43,7 @@ fn skips_bad_dir() -> TestResult {
 #[test]
 fn dies_bad_name() -> TestResult {
     Command::cargo_bin(PRG)?
-        .args(&["--name", "*.csv"])
+        .args(["--name", "*.csv"])
         .assert()
         .failure()
         .stderr(predicate::str::contains("Invalid --name \"*.csv\""));
@@ -51,7 +55,7 @@ fn dies_bad_name() -> TestResult {
 fn dies_bad_type() -> TestResult {
     let expected = "error: 'x' isn't a valid value for '--type <TYPE>...'";
     Command::cargo_bin(PRG)?
-        .args(&["--type", "x"])
+        .args(["--type", "x"])
         .assert()
         .failure()
         .stderr(predicate::str::contains(expected));
@@ -60,14 +64,14 @@ fn dies_bad_type() -> TestResult {
 
 // --------------------------------------------------
 #[cfg(windows)]
-fn format_file_name(expected_file: &str) -> Cow<str> {
+fn format_file_name(expected_file: &str) -> Cow<'_, str> {
     // Equivalent to: Cow::Owned(format!("{}.windows", expected_file))
     format!("{}.windows", expected_file).into()
 }
 
 // --------------------------------------------------
 #[cfg(not(windows))]
-fn format_file_name(expected_file: &str) -> Cow<str> {
+fn format_file_name(expected_file: &str) -> Cow<'_, str> {
     // Equivalent to: Cow::Borrowed(expected_file)
     expected_file.into()
 }
@@ -285,6 +289,11 @@ fn path_g() -> TestResult {
 #[test]
 #[cfg(not(windows))]
 fn unreadable_dir() -> TestResult {
+    // permissions don't stop root reading anything
+    if uzers::get_effective_uid() == 0 {
+        return Ok(());
+    }
+
     let dirname = "tests/inputs/cant-touch-this";
     if !Path::new(dirname).exists() {
         fs::create_dir(dirname)?;
@@ -295,17 +304,15 @@ fn unreadable_dir() -> TestResult {
     //permissions.set_mode(0o000);
 
     std::process::Command::new("chmod")
-        .args(&["000", dirname])
+        .args(["000", dirname])
         .status()
         .expect("failed");
 
-    let cmd = Command::cargo_bin(PRG)?
-        .arg("tests/inputs")
-        .assert()
-        .success();
+    let cmd = Command::cargo_bin(PRG)?.arg("tests/inputs").assert();
     fs::remove_dir(dirname)?;
 
-    let out = cmd.get_output();
+    // the rest of the walk goes on, but findr fails at the end
+    let out = cmd.failure().get_output().clone();
     let stdout = String::from_utf8(out.stdout.clone())?;
     let lines: Vec<&str> =
         stdout.split("\n").filter(|s| !s.is_empty()).collect();
@@ -313,6 +320,1652 @@ fn unreadable_dir() -> TestResult {
     assert_eq!(lines.len(), 17);
 
     let stderr = String::from_utf8(out.stderr.clone())?;
-    assert!(stderr.contains("cant-touch-this: Permission denied"));
+    assert!(stderr
+        .contains("findr: tests/inputs/cant-touch-this: Permission denied\n"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_max_depth() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--max-depth", "x"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid --max-depth \"x\""));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn max_depth_1() -> TestResult {
+    run(
+        &["tests/inputs", "--max-depth", "1"],
+        "tests/expected/max_depth_1.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn min_depth_2_max_depth_2() -> TestResult {
+    run(
+        &["tests/inputs", "--min-depth", "2", "--max-depth", "2"],
+        "tests/expected/min_depth_2_max_depth_2.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn max_depth_1_path_a_d() -> TestResult {
+    run(
+        &["tests/inputs/a", "tests/inputs/d", "--max-depth", "1"],
+        "tests/expected/max_depth_1_path_a_d.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn min_depth_1_type_d_path_a_d() -> TestResult {
+    run(
+        &[
+            "tests/inputs/a",
+            "tests/inputs/d",
+            "--min-depth",
+            "1",
+            "-t",
+            "d",
+        ],
+        "tests/expected/min_depth_1_type_d_path_a_d.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_size() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--size", "+1x"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid --size \"+1x\""));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_mtime() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--mtime", "--1"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid --mtime \"--1\""));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_newer() -> TestResult {
+    let bad = gen_bad_file();
+    let expected = format!("{}: .* [(]os error 2[)]", &bad);
+    Command::cargo_bin(PRG)?
+        .args(["--newer", &bad])
+        .assert()
+        .failure()
+        .stderr(predicate::str::is_match(expected)?);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn size_2_type_f() -> TestResult {
+    run(
+        &["tests/inputs", "--size", "2", "-t", "f"],
+        "tests/expected/size_2_type_f.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn size_minus_1k() -> TestResult {
+    // sizes round up to the unit, so only empty entries are under 1k
+    run(
+        &["tests/inputs", "--size", "-1k"],
+        "tests/expected/size_minus_1k.txt",
+    )
+}
+
+// --------------------------------------------------
+// builds a tree with an old empty file, a fresh file and an empty dir
+fn time_fixture() -> Fixture {
+    let dir = tempfile::tempdir()?;
+    let old = dir.path().join("old.txt");
+    fs::write(&old, "")?;
+    fs::write(dir.path().join("new.txt"), "new\n")?;
+    fs::create_dir(dir.path().join("empty"))?;
+
+    let days_ago = |days: i64| {
+        let now = filetime::FileTime::now();
+        filetime::FileTime::from_unix_time(now.unix_seconds() - days * 86400, 0)
+    };
+    filetime::set_file_times(&old, days_ago(40), days_ago(40))?;
+    Ok(dir)
+}
+
+// --------------------------------------------------
+// runs findr in dir, sorting its output unless the order is being tested
+fn run_in(
+    dir: &Path,
+    args: &[&str],
+    sort: bool,
+) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let cmd = Command::cargo_bin(PRG)?
+        .arg(dir)
+        .args(args)
+        .assert()
+        .success();
+    let stdout = String::from_utf8(cmd.get_output().stdout.clone())?;
+    let mut lines: Vec<String> = stdout
+        .lines()
+        .map(|line| {
+            let path = Path::new(line);
+            path.strip_prefix(dir).unwrap_or(path).display().to_string()
+        })
+        .collect();
+    if sort {
+        lines.sort();
+    }
+    Ok(lines)
+}
+
+// --------------------------------------------------
+#[test]
+fn mtime_more_than_30() -> TestResult {
+    let dir = time_fixture()?;
+    assert_eq!(run_in(dir.path(), &["--mtime", "+30"], true)?, ["old.txt"]);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn atime_less_than_1_type_f() -> TestResult {
+    let dir = time_fixture()?;
+    let found = run_in(dir.path(), &["--atime", "-1", "-t", "f"], true)?;
+    assert_eq!(found, ["new.txt"]);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn ctime_0_type_f() -> TestResult {
+    // setting the times changes the status, so both files are fresh
+    let dir = time_fixture()?;
+    let found = run_in(dir.path(), &["--ctime", "0", "-t", "f"], true)?;
+    assert_eq!(found, ["new.txt", "old.txt"]);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn newer_old() -> TestResult {
+    let dir = time_fixture()?;
+    let old = dir.path().join("old.txt");
+    let found = run_in(
+        dir.path(),
+        &["--newer", &old.to_string_lossy(), "-t", "f"],
+        true,
+    )?;
+    assert_eq!(found, ["new.txt"]);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn empty() -> TestResult {
+    let dir = time_fixture()?;
+    assert_eq!(
+        run_in(dir.path(), &["--empty"], true)?,
+        ["empty", "old.txt"]
+    );
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn size_more_than_2_mtime_more_than_30() -> TestResult {
+    let dir = time_fixture()?;
+    let found = run_in(dir.path(), &["--size", "+2", "--mtime", "+30"], true)?;
+    assert!(found.is_empty());
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_unmatched_paren() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["(", "-name", "a"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Missing \")\" in expression"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_missing_operand() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-name", "a", "-or"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Expected a test after \"-or\""));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_expr_type() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-type", "x"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid -type \"x\""));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn expr_or_not() -> TestResult {
+    run(
+        &[
+            "tests/inputs",
+            "(",
+            "-name",
+            "*.csv",
+            "-or",
+            "-name",
+            "*.mp3",
+            ")",
+            "-not",
+            "-name",
+            "b*",
+        ],
+        "tests/expected/expr_or_not.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn expr_and_or() -> TestResult {
+    // -and binds tighter than -or
+    run(
+        &[
+            "tests/inputs",
+            "-type",
+            "f",
+            "-name",
+            "*a*",
+            "-o",
+            "-type",
+            "d",
+            "-name",
+            "b",
+        ],
+        "tests/expected/expr_and_or.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn expr_not_max_depth_1() -> TestResult {
+    run(
+        &["tests/inputs", "--max-depth", "1", "!", "-type", "d"],
+        "tests/expected/expr_not_max_depth_1.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn expr_and_name_option() -> TestResult {
+    // options are ANDed with the expression
+    run(
+        &[
+            "tests/inputs",
+            "-n",
+            "csv$",
+            "-type",
+            "f",
+            "-or",
+            "-type",
+            "l",
+        ],
+        "tests/expected/name_csv.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_unterminated_exec() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-exec", "echo", "{}"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Missing \";\" or \"+\" to end -exec",
+        ));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_ok_batch() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-ok", "echo", "{}", "+"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("-ok only supports \";\""));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn exec_echo_path_a() -> TestResult {
+    run(
+        &[
+            "tests/inputs/a",
+            "-type",
+            "f",
+            "-exec",
+            "echo",
+            "X",
+            "{}",
+            ";",
+        ],
+        "tests/expected/exec_echo_path_a.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn exec_test_link() -> TestResult {
+    // the exit status of the command is the result of -exec
+    run(
+        &[
+            "tests/inputs",
+            "-name",
+            "*.csv",
+            "-exec",
+            "test",
+            "-L",
+            "{}",
+            ";",
+            "-print",
+        ],
+        "tests/expected/exec_test_link.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn exec_batch() -> TestResult {
+    let cmd = Command::cargo_bin(PRG)?
+        .args(["tests/inputs", "-type", "f", "-exec", "echo", "{}", "+"])
+        .assert()
+        .success();
+    let stdout = String::from_utf8(cmd.get_output().stdout.clone())?;
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines.len(), 1);
+
+    let mut paths: Vec<&str> = lines[0].split(' ').collect();
+    paths.sort();
+    let contents = fs::read_to_string("tests/expected/type_f.txt")?;
+    let mut expected: Vec<&str> = contents.lines().collect();
+    expected.sort();
+    assert_eq!(paths, expected);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn exec_batch_fails() -> TestResult {
+    // a failing batch has no match to return false for, but still counts
+    Command::cargo_bin(PRG)?
+        .args(["tests/inputs/a", "-type", "f", "-exec", "false", "{}", "+"])
+        .assert()
+        .failure()
+        .stdout("")
+        .stderr("");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn ok_yes() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["tests/inputs/g.csv", "-ok", "echo", "hi", "{}", ";"])
+        .write_stdin("y\n")
+        .assert()
+        .success()
+        .stdout("hi tests/inputs/g.csv\n")
+        .stderr("< echo hi tests/inputs/g.csv > ? ");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn ok_no() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["tests/inputs/g.csv", "-ok", "echo", "hi", "{}", ";"])
+        .write_stdin("n\n")
+        .assert()
+        .success()
+        .stdout("");
+    Ok(())
+}
+
+// --------------------------------------------------
+// builds a tree with some .tmp files to clean up
+fn delete_fixture() -> Fixture {
+    let dir = tempfile::tempdir()?;
+    fs::create_dir_all(dir.path().join("a/b"))?;
+    fs::create_dir(dir.path().join("c"))?;
+    for file in ["a/x.tmp", "a/keep.txt", "a/b/y.tmp", "c/z.tmp"] {
+        fs::write(dir.path().join(file), "")?;
+    }
+    Ok(dir)
+}
+
+// --------------------------------------------------
+fn remaining(dir: &Path) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let mut found = vec![];
+    for entry in walkdir::WalkDir::new(dir).min_depth(1) {
+        let path = entry?.into_path();
+        found.push(path.strip_prefix(dir)?.display().to_string());
+    }
+    found.sort();
+    Ok(found)
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_delete_without_test() -> TestResult {
+    let dir = delete_fixture()?;
+    Command::cargo_bin(PRG)?
+        .arg(dir.path())
+        .arg("-delete")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Refusing to -delete every entry without a test, use --force",
+        ));
+    assert_eq!(remaining(dir.path())?.len(), 7);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_prune_delete() -> TestResult {
+    // the pruned directory's contents would already have been deleted
+    let dir = delete_fixture()?;
+    Command::cargo_bin(PRG)?
+        .arg(dir.path())
+        .args(["-name", "a", "-prune", "-o", "-name", "*.tmp", "-delete"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "-prune has no effect with -delete",
+        ));
+    assert_eq!(remaining(dir.path())?.len(), 7);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn delete_name_tmp() -> TestResult {
+    let dir = delete_fixture()?;
+    assert!(
+        run_in(dir.path(), &["-name", "*.tmp", "-delete"], true)?.is_empty()
+    );
+    assert_eq!(remaining(dir.path())?, ["a", "a/b", "a/keep.txt", "c"]);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn delete_dry_run() -> TestResult {
+    let dir = delete_fixture()?;
+    let found = run_in(
+        dir.path(),
+        &["-name", "*.tmp", "-delete", "--dry-run"],
+        true,
+    )?;
+    assert_eq!(found, ["a/b/y.tmp", "a/x.tmp", "c/z.tmp"]);
+    assert_eq!(remaining(dir.path())?.len(), 7);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn delete_force_depth_first() -> TestResult {
+    // directories are only removed once their contents are gone
+    let dir = delete_fixture()?;
+    run_in(
+        dir.path(),
+        &["--min-depth", "1", "-delete", "--force"],
+        true,
+    )?;
+    assert!(remaining(dir.path())?.is_empty());
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn delete_non_empty_dir() -> TestResult {
+    let dir = delete_fixture()?;
+    Command::cargo_bin(PRG)?
+        .arg(dir.path())
+        .args(["-type", "d", "-name", "a", "-delete"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("a: Directory not empty\n"));
+    assert_eq!(remaining(dir.path())?.len(), 7);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_printf() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-printf", "%z"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid -printf directive \"%z\""));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn printf_type_f_l() -> TestResult {
+    run(
+        &[
+            "tests/inputs",
+            "-type",
+            "l",
+            "-printf",
+            "%f %h %s %y %d\\n",
+            "-o",
+            "-type",
+            "f",
+            "-printf",
+            "%p %f %h %s %y %d\\n",
+        ],
+        "tests/expected/printf_type_f_l.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn printf_mode() -> TestResult {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = tempfile::tempdir()?;
+    let file = dir.path().join("file");
+    fs::write(&file, "")?;
+    fs::set_permissions(&file, fs::Permissions::from_mode(0o640))?;
+    fs::set_permissions(dir.path(), fs::Permissions::from_mode(0o1777))?;
+
+    Command::cargo_bin(PRG)?
+        .arg(dir.path())
+        .args(["-printf", "%m %M %%\\t%f\\n"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("640 -rw-r----- %\tfile\n"))
+        .stdout(predicate::str::contains("1777 drwxrwxrwt %\t"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn print0() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["tests/inputs/a/b", "-print0"])
+        .assert()
+        .success()
+        .stdout(predicate::function(|out: &str| {
+            let mut paths: Vec<&str> = out.split_terminator('\0').collect();
+            paths.sort();
+            paths
+                == [
+                    "tests/inputs/a/b",
+                    "tests/inputs/a/b/b.csv",
+                    "tests/inputs/a/b/c",
+                    "tests/inputs/a/b/c/c.mp3",
+                ]
+        }));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn closed_pipe() -> TestResult {
+    use std::io::Read;
+    use std::process::Stdio;
+
+    // far more output than a pipe holds, so writing runs into its end
+    let dir = tempfile::tempdir()?;
+    for i in 0..2000 {
+        fs::write(dir.path().join(format!("{:0100}", i)), "")?;
+    }
+    for action in [&["-print"][..], &["-print0"], &["-printf", "%p\\n"]] {
+        let mut child =
+            std::process::Command::new(assert_cmd::cargo::cargo_bin(PRG))
+                .arg(dir.path())
+                .args(action)
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped())
+                .spawn()?;
+        let mut start = [0; 5];
+        child.stdout.take().unwrap().read_exact(&mut start)?;
+        let output = child.wait_with_output()?;
+        assert!(output.status.success(), "{:?}", action);
+        assert_eq!(String::from_utf8(output.stderr)?, "");
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_glob() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-name", "[a"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid -name \"[a\""));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn glob_name_csv() -> TestResult {
+    run(
+        &["tests/inputs", "-name", "*.csv"],
+        "tests/expected/name_csv.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn iname_csv() -> TestResult {
+    run(
+        &["tests/inputs", "-iname", "*.CSV"],
+        "tests/expected/iname_csv.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn path_b() -> TestResult {
+    // unlike -name, -path sees the directories leading to the entry
+    run(
+        &["tests/inputs", "-path", "*/b/*"],
+        "tests/expected/path_b.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn ipath_depth_1() -> TestResult {
+    run(
+        &["tests/inputs", "-ipath", "TESTS/INPUTS/?"],
+        "tests/expected/ipath_depth_1.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn regex_is_anchored() -> TestResult {
+    // "c" would match c.mp3 if it weren't anchored to the whole path
+    Command::cargo_bin(PRG)?
+        .args(["tests/inputs", "-regex", "c"])
+        .assert()
+        .success()
+        .stdout("");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn iregex_a_d_t() -> TestResult {
+    run(
+        &["tests/inputs", "-iregex", ".*/[A-D][.]T.*"],
+        "tests/expected/iregex_a_d_t.txt",
+    )
+}
+
+// --------------------------------------------------
+// builds a tree with a link to a directory, a link back to a parent and
+// a dangling link, plus a link to the whole tree
+fn link_fixture() -> Fixture {
+    use std::os::unix::fs::symlink;
+
+    let dir = tempfile::tempdir()?;
+    let top = dir.path().join("top");
+    fs::create_dir_all(top.join("real"))?;
+    fs::write(top.join("real/file"), "")?;
+    symlink("real", top.join("link"))?;
+    symlink("..", top.join("real/loop"))?;
+    symlink("nowhere", top.join("dangling"))?;
+    symlink("top", dir.path().join("rootlink"))?;
+    Ok(dir)
+}
+
+// --------------------------------------------------
+#[test]
+fn links_not_followed() -> TestResult {
+    let dir = link_fixture()?;
+    let found = run_in(&dir.path().join("top"), &["-type", "l"], true)?;
+    assert_eq!(found, ["dangling", "link", "real/loop"]);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn follow_links() -> TestResult {
+    let dir = link_fixture()?;
+    let top = dir.path().join("top");
+    let cmd = Command::cargo_bin(PRG)?
+        .arg("-L")
+        .arg(&top)
+        .assert()
+        .failure();
+    let out = cmd.get_output();
+    let stdout = String::from_utf8(out.stdout.clone())?;
+    let mut found: Vec<String> = stdout
+        .lines()
+        .map(|line| {
+            Path::new(line)
+                .strip_prefix(&top)
+                .map(|p| p.display().to_string())
+        })
+        .collect::<Result<_, _>>()?;
+    found.sort();
+    // the broken link is still found, and the loop is reported instead
+    assert_eq!(
+        found,
+        ["", "dangling", "link", "link/file", "real", "real/file"]
+    );
+
+    let stderr = String::from_utf8(out.stderr.clone())?;
+    assert_eq!(stderr.matches("File system loop detected").count(), 2);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn follow_links_type_l() -> TestResult {
+    // only the broken link is still a link once links are followed
+    let dir = link_fixture()?;
+    let top = dir.path().join("top");
+    Command::cargo_bin(PRG)?
+        .args(["-L", "-type", "l", "--quiet-errors"])
+        .arg(&top)
+        .assert()
+        .failure()
+        .stdout(format!("{}\n", top.join("dangling").display()));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn follow_root_links() -> TestResult {
+    let dir = link_fixture()?;
+    let root = dir.path().join("rootlink");
+    assert_eq!(run_in(&root, &[], true)?, [""]);
+
+    let found = run_in(&root, &["-H", "-type", "l"], true)?;
+    assert_eq!(found, ["dangling", "link", "real/loop"]);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn xdev() -> TestResult {
+    use std::os::unix::fs::MetadataExt;
+
+    // only meaningful where /proc is mounted separately from /
+    let (root, proc) = match (fs::metadata("/"), fs::metadata("/proc/self")) {
+        (Ok(root), Ok(proc)) => (root, proc),
+        _ => return Ok(()),
+    };
+    if root.dev() == proc.dev() {
+        return Ok(());
+    }
+
+    let run_proc = |flag: &str| -> Result<String, Box<dyn std::error::Error>> {
+        let cmd = Command::cargo_bin(PRG)?
+            .args(["/", flag, "--max-depth", "2", "-path", "/proc/*"])
+            .assert()
+            .success();
+        Ok(String::from_utf8(cmd.get_output().stdout.clone())?)
+    };
+    assert!(!run_proc("-true")?.is_empty());
+    assert!(run_proc("-xdev")?.is_empty());
+    assert!(run_proc("--xdev")?.is_empty());
+    Ok(())
+}
+
+// --------------------------------------------------
+// builds files with the given permissions, a fifo and a socket
+fn perm_fixture() -> Fixture {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = tempfile::tempdir()?;
+    for (file, mode) in
+        [("a", 0o644), ("b", 0o666), ("c", 0o4755), ("d", 0o600)]
+    {
+        let path = dir.path().join(file);
+        fs::write(&path, "")?;
+        fs::set_permissions(&path, fs::Permissions::from_mode(mode))?;
+    }
+    std::process::Command::new("mkfifo")
+        .arg(dir.path().join("fifo"))
+        .status()?;
+    std::os::unix::net::UnixListener::bind(dir.path().join("sock"))?;
+    Ok(dir)
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_perm() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-perm", "u+q"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid -perm \"u+q\""));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_unknown_user() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-user", "no-such-user-here"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Unknown user \"no-such-user-here\"",
+        ));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn type_p_s() -> TestResult {
+    let dir = perm_fixture()?;
+    assert_eq!(run_in(dir.path(), &["-type", "p"], true)?, ["fifo"]);
+    assert_eq!(run_in(dir.path(), &["--type", "s"], true)?, ["sock"]);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn type_c() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["/dev/null", "-type", "c"])
+        .assert()
+        .success()
+        .stdout("/dev/null\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn perm_exact() -> TestResult {
+    let dir = perm_fixture()?;
+    let found = run_in(dir.path(), &["-type", "f", "-perm", "644"], true)?;
+    assert_eq!(found, ["a"]);
+    let found =
+        run_in(dir.path(), &["-type", "f", "-perm", "u=rw,go=r"], true)?;
+    assert_eq!(found, ["a"]);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn perm_all() -> TestResult {
+    // world-writable
+    let dir = perm_fixture()?;
+    assert_eq!(
+        run_in(dir.path(), &["-type", "f", "-perm", "-002"], true)?,
+        ["b"]
+    );
+    assert_eq!(
+        run_in(dir.path(), &["-type", "f", "-perm", "-o+w"], true)?,
+        ["b"]
+    );
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn perm_any() -> TestResult {
+    let dir = perm_fixture()?;
+    assert_eq!(run_in(dir.path(), &["-perm", "/u+s"], true)?, ["c"]);
+    let found = run_in(dir.path(), &["-type", "f", "-perm", "/055"], true)?;
+    assert_eq!(found, ["a", "b", "c"]);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn uid_gid_inum() -> TestResult {
+    use std::os::unix::fs::MetadataExt;
+
+    let dir = perm_fixture()?;
+    let metadata = fs::metadata(dir.path().join("a"))?;
+    let uid = metadata.uid().to_string();
+    let gid = metadata.gid().to_string();
+    let ino = metadata.ino().to_string();
+
+    assert_eq!(run_in(dir.path(), &["-inum", &ino], true)?, ["a"]);
+    let found = run_in(dir.path(), &["-type", "f", "-uid", &uid], true)?;
+    assert_eq!(found, ["a", "b", "c", "d"]);
+    let found = run_in(dir.path(), &["-type", "f", "-user", &uid], true)?;
+    assert_eq!(found, ["a", "b", "c", "d"]);
+    let found = run_in(dir.path(), &["-type", "f", "-group", &gid], true)?;
+    assert_eq!(found, ["a", "b", "c", "d"]);
+    assert!(run_in(dir.path(), &["-type", "f", "-gid", "-0"], true)?.is_empty());
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn nouser_nogroup() -> TestResult {
+    let dir = perm_fixture()?;
+    let orphan = dir.path().join("orphan");
+    fs::write(&orphan, "")?;
+    // handing a file to an unknown owner needs root
+    if std::os::unix::fs::chown(&orphan, Some(424242), Some(434343)).is_err() {
+        return Ok(());
+    }
+    assert_eq!(run_in(dir.path(), &["-nouser"], true)?, ["orphan"]);
+    assert_eq!(run_in(dir.path(), &["-nogroup"], true)?, ["orphan"]);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_exclude_dir() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--exclude-dir", "["])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid --exclude-dir \"[\""));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn prune_b() -> TestResult {
+    run(
+        &["tests/inputs", "-name", "b", "-prune", "-o", "-print"],
+        "tests/expected/prune_b.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn prune_prints_match() -> TestResult {
+    // -prune isn't an action, so the pruned directory is still printed
+    run(
+        &["tests/inputs", "-type", "d", "-name", "a", "-prune"],
+        "tests/expected/prune_a_only.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn exclude_dir_b_e() -> TestResult {
+    run(
+        &["tests/inputs", "--exclude-dir", "b", "--exclude-dir", "e*"],
+        "tests/expected/exclude_dir_b_e.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn exclude_dir_keeps_start() -> TestResult {
+    run(
+        &["tests/inputs/a/b", "--exclude-dir", "b"],
+        "tests/expected/path_a_b.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn exclude_dir_delete() -> TestResult {
+    // excluded directories are left alone however the walk is ordered
+    for walk in [
+        &[][..],
+        &["--sort", "name"],
+        &["-j", "2"],
+        &["--respect-ignore"],
+    ] {
+        let dir = delete_fixture()?;
+        let mut args = vec!["--exclude-dir", "b", "-name", "*.tmp", "-delete"];
+        args.extend(walk);
+        run_in(dir.path(), &args, true)?;
+        let expected = ["a", "a/b", "a/b/y.tmp", "a/keep.txt", "c"];
+        assert_eq!(remaining(dir.path())?, expected, "{:?}", walk);
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn respect_ignore() -> TestResult {
+    // tests/ignore is inside this git checkout, so its .gitignore applies
+    assert_eq!(
+        run_in(Path::new("tests/ignore"), &["--respect-ignore"], true)?,
+        ["", "src", "src/main.rs"]
+    );
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn ignores_nothing_by_default() -> TestResult {
+    let found = run_in(Path::new("tests/ignore"), &["-type", "f"], true)?;
+    assert_eq!(
+        found,
+        [
+            ".gitignore",
+            ".hidden",
+            ".ignore",
+            "build.log",
+            "notes.txt",
+            "src/debug.log",
+            "src/main.rs",
+            "target/debug/findr"
+        ]
+    );
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn respect_ignore_prune() -> TestResult {
+    let args = ["--respect-ignore", "-name", "src", "-prune", "-o", "-print"];
+    assert_eq!(run_in(Path::new("tests/ignore"), &args, true)?, [""]);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn respect_ignore_exclude_dir() -> TestResult {
+    let args = ["--respect-ignore", "--exclude-dir", "src"];
+    assert_eq!(run_in(Path::new("tests/ignore"), &args, true)?, [""]);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn respect_ignore_root_link() -> TestResult {
+    let dir = link_fixture()?;
+    let root = dir.path().join("rootlink");
+    assert_eq!(run_in(&root, &["--respect-ignore"], true)?, [""]);
+
+    let found = run_in(&root, &["--respect-ignore", "-H", "-type", "l"], true)?;
+    assert_eq!(found, ["dangling", "link", "real/loop"]);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn threads_path1() -> TestResult {
+    run(&["tests/inputs", "-j", "4"], "tests/expected/path1.txt")
+}
+
+// --------------------------------------------------
+#[test]
+fn threads_root_link() -> TestResult {
+    let dir = link_fixture()?;
+    let root = dir.path().join("rootlink");
+    assert_eq!(run_in(&root, &["-j", "2"], true)?, [""]);
+
+    let found = run_in(&root, &["-j", "2", "-H", "-type", "l"], true)?;
+    assert_eq!(found, ["dangling", "link", "real/loop"]);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn threads_prune_b() -> TestResult {
+    run(
+        &[
+            "tests/inputs",
+            "-j",
+            "4",
+            "-name",
+            "b",
+            "-prune",
+            "-o",
+            "-print",
+        ],
+        "tests/expected/prune_b.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn threads_sort() -> TestResult {
+    // the output itself is in order, unlike what run compares
+    let mut expected: Vec<String> =
+        fs::read_to_string("tests/expected/path1.txt")?
+            .lines()
+            .map(str::to_string)
+            .collect();
+    expected.sort();
+    let cmd = Command::cargo_bin(PRG)?
+        .args(["tests/inputs", "-j", "4", "--sort", "name"])
+        .assert()
+        .success();
+    let stdout = String::from_utf8(cmd.get_output().stdout.clone())?;
+    assert_eq!(stdout.lines().collect::<Vec<_>>(), expected);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn threads_delete_depth_first() -> TestResult {
+    let dir = delete_fixture()?;
+    let args = ["-j", "4", "--min-depth", "1", "-delete", "--force"];
+    run_in(dir.path(), &args, true)?;
+    assert!(remaining(dir.path())?.is_empty());
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_threads() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-j", "0"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid --threads \"0\""));
+    Ok(())
+}
+
+// --------------------------------------------------
+// builds files whose sizes and mtimes run opposite to their names
+fn sort_fixture() -> Fixture {
+    let dir = tempfile::tempdir()?;
+    fs::create_dir(dir.path().join("d"))?;
+    for (i, file) in ["a", "b", "d/x", "d/y"].into_iter().enumerate() {
+        let path = dir.path().join(file);
+        fs::write(&path, "x".repeat(10 - i))?;
+        let mtime = filetime::FileTime::from_unix_time(1_000_000 - i as i64, 0);
+        filetime::set_file_mtime(&path, mtime)?;
+    }
+    filetime::set_file_mtime(
+        dir.path().join("d"),
+        filetime::FileTime::from_unix_time(1, 0),
+    )?;
+    Ok(dir)
+}
+
+// --------------------------------------------------
+#[test]
+fn sort_name() -> TestResult {
+    // tests/sort holds the same names and sizes as sort_fixture
+    let dir = Path::new("tests/sort");
+    let found = run_in(dir, &["--min-depth", "1", "--sort", "name"], false)?;
+    assert_eq!(found, ["a", "b", "d", "d/x", "d/y"]);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn sort_size() -> TestResult {
+    // unlike names, sizes order the whole walk
+    let dir = Path::new("tests/sort");
+    let args = ["--min-depth", "1", "-type", "f", "--sort", "size"];
+    assert_eq!(run_in(dir, &args, false)?, ["d/y", "d/x", "b", "a"]);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn sort_size_prune() -> TestResult {
+    // d's contents sort ahead of it, but pruning it still leaves them out
+    let dir = Path::new("tests/sort");
+    let args = [
+        "--sort", "size", "-name", "d", "-prune", "-o", "-type", "f", "-print",
+    ];
+    assert_eq!(run_in(dir, &args, false)?, ["b", "a"]);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn sort_mtime() -> TestResult {
+    let dir = sort_fixture()?;
+    let found =
+        run_in(dir.path(), &["--min-depth", "1", "--sort", "mtime"], false)?;
+    assert_eq!(found, ["d", "d/y", "d/x", "b", "a"]);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn sort_mtime_delete() -> TestResult {
+    let dir = sort_fixture()?;
+    let args = ["--min-depth", "1", "--sort", "mtime", "-delete", "--force"];
+    run_in(dir.path(), &args, true)?;
+    assert!(remaining(dir.path())?.is_empty());
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn json() -> TestResult {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = sort_fixture()?;
+    let path = dir.path().join("b");
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o640))?;
+    let found = run_in(dir.path(), &["-name", "b", "--json"], false)?;
+    let value: serde_json::Value = serde_json::from_str(&found[0])?;
+    assert_eq!(
+        value,
+        serde_json::json!({
+            "path": path.to_string_lossy(),
+            "type": "file",
+            "size": 9,
+            "mode": "640",
+            "mtime": 999_999,
+            "depth": 1,
+        })
+    );
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn ls() -> TestResult {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = sort_fixture()?;
+    let path = dir.path().join("a");
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o640))?;
+    let found = run_in(dir.path(), &["-name", "a", "-ls"], false)?;
+    let fields: Vec<_> = found[0].split_whitespace().collect();
+    assert_eq!(fields[2], "-rw-r-----");
+    assert_eq!(fields[6], "10");
+    // 1970 is more than six months ago, so the year is shown
+    assert_eq!(fields[9], "1970");
+    assert_eq!(fields[10], path.display().to_string());
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn duplicates() -> TestResult {
+    // the larger group comes first, and the empty files are left out
+    let expected = fs::read_to_string("tests/expected/duplicates.txt")?;
+    Command::cargo_bin(PRG)?
+        .args(["tests/dupes", "--duplicates"])
+        .assert()
+        .success()
+        .stdout(expected);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn duplicates_min_size_json() -> TestResult {
+    let expected =
+        fs::read_to_string("tests/expected/duplicates_min_size_json.txt")?;
+    Command::cargo_bin(PRG)?
+        .args(["tests/dupes", "--duplicates", "--dup-min-size", "1k"])
+        .arg("--json")
+        .assert()
+        .success()
+        .stdout(expected);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn duplicates_of_matches() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["tests/dupes", "--duplicates", "-name", "*.txt"])
+        .args(["-not", "-path", "*/sub/*"])
+        .assert()
+        .success()
+        .stdout("");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn duplicates_hard_link() -> TestResult {
+    // a hard link takes no extra space, so it is no duplicate
+    let dir = tempfile::tempdir()?;
+    fs::write(dir.path().join("a.txt"), "hello")?;
+    fs::write(dir.path().join("b.txt"), "hello")?;
+    fs::hard_link(dir.path().join("a.txt"), dir.path().join("z.txt"))?;
+    let found = run_in(dir.path(), &["--duplicates"], false)?;
+    assert_eq!(found, ["a.txt", "b.txt"]);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_dup_min_size() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--duplicates", "--dup-min-size", "1x"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid --dup-min-size \"1x\""));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn contains() -> TestResult {
+    run(
+        &["tests/content", "-contains", "ne+dle"],
+        "tests/expected/contains.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn contains_anchored_line() -> TestResult {
+    run(
+        &["tests/content", "-contains", "^needle"],
+        "tests/expected/contains_anchored_line.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn contains_line_end() -> TestResult {
+    run(
+        &["tests/content", "-contains", "here$"],
+        "tests/expected/contains_anchored_line.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn contains_long_line() -> TestResult {
+    // a line far longer than what is read at once, with the match
+    // straddling the end of the first piece
+    let dir = tempfile::tempdir()?;
+    let mut line = vec![b'x'; 3 * 1024 * 1024];
+    line[1024 * 1024 - 3..][..6].copy_from_slice(b"needle");
+    fs::write(dir.path().join("long"), &line)?;
+    fs::write(dir.path().join("other"), &line[..1024 * 1024])?;
+    assert_eq!(
+        run_in(dir.path(), &["-contains", "needle"], true)?,
+        ["long"]
+    );
+    // the first piece of the long line ends in "nee" too, but not the line
+    assert_eq!(run_in(dir.path(), &["-contains", "e$"], true)?, ["other"]);
+    assert!(run_in(dir.path(), &["-contains", "^needle"], true)?.is_empty());
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn mime_image() -> TestResult {
+    run(
+        &["tests/content", "-mime", "image/*"],
+        "tests/expected/mime_image.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn mime_text_plain() -> TestResult {
+    run(
+        &["tests/content", "-mime", "text/plain"],
+        "tests/expected/mime_text_plain.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn mime_octet_stream() -> TestResult {
+    run(
+        &["tests/content", "-mime", "application/octet-stream"],
+        "tests/expected/mime_octet_stream.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_contains() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-contains", "("])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid -contains \"(\""));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn missing_path() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["tests/inputs/g.csv", "tests/inputs/nope", "tests/inputs/a"])
+        .args(["--max-depth", "0"])
+        .assert()
+        .failure()
+        .stdout("tests/inputs/g.csv\ntests/inputs/a\n")
+        .stderr("findr: tests/inputs/nope: No such file or directory\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn quiet_errors() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["tests/inputs/nope", "tests/inputs/g.csv", "--quiet-errors"])
+        .assert()
+        .failure()
+        .stdout("tests/inputs/g.csv\n")
+        .stderr("");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn vanished_file() -> TestResult {
+    // the file is gone by the time -size looks at it
+    let dir = tempfile::tempdir()?;
+    let file = dir.path().join("gone");
+    fs::write(&file, "")?;
+    Command::cargo_bin(PRG)?
+        .arg(dir.path())
+        .args(["-type", "f", "-exec", "rm", "{}", ";", "-size", "0"])
+        .assert()
+        .failure()
+        .stdout("")
+        .stderr(format!(
+            "findr: {}: No such file or directory\n",
+            file.display()
+        ));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn symlink_loop() -> TestResult {
+    let dir = tempfile::tempdir()?;
+    fs::create_dir(dir.path().join("d"))?;
+    std::os::unix::fs::symlink("..", dir.path().join("d/up"))?;
+    for threads in ["1", "2"] {
+        let loop_path = dir.path().join("d/up");
+        Command::cargo_bin(PRG)?
+            .arg("-L")
+            .arg(dir.path())
+            .args(["-j", threads])
+            .assert()
+            .failure()
+            .stderr(format!(
+                "findr: {}: File system loop detected\n",
+                loop_path.display()
+            ));
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn exec_missing_command() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["tests/inputs/g.csv", "-exec", "no-such-command", "{}", ";"])
+        .assert()
+        .failure()
+        .stderr("findr: no-such-command: No such file or directory\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn watch() -> TestResult {
+    use std::io::BufRead;
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    let dir = tempfile::tempdir()?;
+    let path = dir.path();
+    fs::create_dir_all(path.join("sub"))?;
+    fs::create_dir_all(path.join("skip"))?;
+    fs::write(path.join("old.txt"), "")?;
+
+    let mut child =
+        std::process::Command::new(assert_cmd::cargo::cargo_bin(PRG))
+            .arg(path)
+            .args(["-name", "skip", "-prune", "-o", "-name", "*.txt", "-print"])
+            .arg("--watch")
+            .stdout(std::process::Stdio::piped())
+            .spawn()?;
+    let (tx, rx) = mpsc::channel();
+    let stdout = child.stdout.take().unwrap();
+    let root = path.to_path_buf();
+    std::thread::spawn(move || {
+        for line in std::io::BufReader::new(stdout).lines() {
+            let line = line.unwrap();
+            let found = Path::new(&line).strip_prefix(&root).unwrap().display();
+            let _ = tx.send(found.to_string());
+        }
+    });
+    // an empty line stands for nothing turning up in time
+    let next = || rx.recv_timeout(Duration::from_secs(5)).unwrap_or_default();
+
+    // the walk comes first, so its output says the watching has begun
+    let initial = next();
+    fs::write(path.join("sub/new.txt"), "")?;
+    fs::write(path.join("sub/new.log"), "")?;
+    fs::write(path.join("skip/pruned.txt"), "")?;
+    fs::write(path.join("tmp"), "")?;
+    fs::rename(path.join("tmp"), path.join("renamed.txt"))?;
+    fs::create_dir_all(path.join("n/m"))?;
+    fs::write(path.join("n/m/deep.txt"), "")?;
+    let mut found = vec![next(), next(), next()];
+    child.kill()?;
+    child.wait()?;
+
+    assert_eq!(initial, "old.txt");
+    found.sort();
+    assert_eq!(found, ["n/m/deep.txt", "renamed.txt", "sub/new.txt"]);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn watch_print0() -> TestResult {
+    use std::io::{BufRead, BufReader};
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    // nothing ends in a newline, so only flushing gets the paths out
+    let dir = tempfile::tempdir()?;
+    let path = dir.path();
+    fs::write(path.join("old.txt"), "")?;
+
+    let mut child =
+        std::process::Command::new(assert_cmd::cargo::cargo_bin(PRG))
+            .arg(path)
+            .args(["-name", "*.txt", "-print0", "--watch"])
+            .stdout(std::process::Stdio::piped())
+            .spawn()?;
+    let (tx, rx) = mpsc::channel();
+    let stdout = child.stdout.take().unwrap();
+    let root = path.to_path_buf();
+    std::thread::spawn(move || {
+        for found in BufReader::new(stdout).split(b'\0') {
+            let found = String::from_utf8(found.unwrap()).unwrap();
+            let found =
+                Path::new(&found).strip_prefix(&root).unwrap().display();
+            let _ = tx.send(found.to_string());
+        }
+    });
+    let next = || rx.recv_timeout(Duration::from_secs(5)).unwrap_or_default();
+
+    let initial = next();
+    fs::write(path.join("new.txt"), "")?;
+    let found = next();
+    child.kill()?;
+    child.wait()?;
+
+    assert_eq!(initial, "old.txt");
+    assert_eq!(found, "new.txt");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn watch_min_depth() -> TestResult {
+    use std::io::{BufRead, BufReader};
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    // a new directory too shallow to be tested is still watched
+    let dir = tempfile::tempdir()?;
+    let path = dir.path();
+    fs::create_dir(path.join("old"))?;
+    fs::write(path.join("old/old.txt"), "")?;
+
+    let mut child =
+        std::process::Command::new(assert_cmd::cargo::cargo_bin(PRG))
+            .arg(path)
+            .args(["--min-depth", "2", "-name", "*.txt", "--watch"])
+            .stdout(std::process::Stdio::piped())
+            .spawn()?;
+    let (tx, rx) = mpsc::channel();
+    let stdout = child.stdout.take().unwrap();
+    let root = path.to_path_buf();
+    std::thread::spawn(move || {
+        for line in BufReader::new(stdout).lines() {
+            let line = line.unwrap();
+            let found = Path::new(&line).strip_prefix(&root).unwrap().display();
+            let _ = tx.send(found.to_string());
+        }
+    });
+    let next = || rx.recv_timeout(Duration::from_secs(5)).unwrap_or_default();
+
+    let initial = next();
+    fs::create_dir(path.join("new"))?;
+    // the file then only turns up through the new directory's own watch
+    std::thread::sleep(Duration::from_millis(200));
+    fs::write(path.join("new/new.txt"), "")?;
+    let found = next();
+    child.kill()?;
+    child.wait()?;
+
+    assert_eq!(initial, "old/old.txt");
+    assert_eq!(found, "new/new.txt");
     Ok(())
 }